@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::error::validate_step_range;
+use crate::segmented::DEFAULT_SEGMENT_LEN;
+use crate::{SieveError, SieveVecBool, progression_within};
+
+/// Estimated number of writes below which `MarkingMode::Auto` stays sequential.
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 1 << 16;
+
+/// How an adaptive marking call is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MarkingMode {
+    /// Pick one of the other modes from the estimated amount of work.
+    #[default]
+    Auto,
+
+    /// Mark on the calling thread, one progression after another.
+    Sequential,
+
+    /// Split each progression into contiguous chunks marked in parallel, one progression after another.
+    Chunked,
+
+    /// Split the buffer into fixed-length segments; each rayon task owns a segment and marks every progression inside it.
+    Segmented,
+}
+
+/**
+Tunes the parallel sieving operations.
+The defaults suit most workloads; set `mode` to force a particular execution strategy, e.g. for benchmarking,
+and `pool` to keep sieving off rayon's global pool.
+
+The adaptive methods such as `SieveVecBool::set_multiples_of_slice_to_false_adaptive` and the `_with` entry points
+such as `primes_up_to_par_with` take a config directly; any other parallel operation can be moved onto the config's pool
+with `install`, e.g. `config.install(|| sieve.set_multiples_to_false_par_checked(n))`.
+*/
+#[derive(Debug, Clone)]
+pub struct SieveConfig {
+    /// The execution strategy, or `MarkingMode::Auto` to choose one per call.
+    pub mode: MarkingMode,
+
+    /// Estimated number of writes below which `MarkingMode::Auto` stays sequential.
+    pub parallel_threshold: usize,
+
+    /// Number of elements each task owns in `MarkingMode::Segmented`.
+    pub segment_len: usize,
+
+    /// The pool parallel work runs on, or `None` for rayon's global pool.
+    pub pool: Option<Arc<ThreadPool>>,
+}
+
+impl Default for SieveConfig {
+    fn default() -> Self {
+        Self {
+            mode: MarkingMode::Auto,
+            parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            segment_len: DEFAULT_SEGMENT_LEN,
+            pool: None,
+        }
+    }
+}
+
+impl SieveConfig {
+    /// Returns the default `SieveConfig`
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns this config with `mode` forced
+    #[must_use]
+    pub const fn with_mode(mut self, mode: MarkingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns this config with the given `parallel_threshold`
+    #[must_use]
+    pub const fn with_parallel_threshold(mut self, parallel_threshold: usize) -> Self {
+        self.parallel_threshold = parallel_threshold;
+        self
+    }
+
+    /// Returns this config with the given `segment_len`, which is raised to 1 if zero
+    #[must_use]
+    pub fn with_segment_len(mut self, segment_len: usize) -> Self {
+        self.segment_len = segment_len.max(1);
+        self
+    }
+
+    /// Returns this config running its parallel work on `pool`
+    #[must_use]
+    pub fn with_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /**
+    Returns this config running its parallel work on a new pool of `num_threads` threads.
+    # Errors
+    Returns the `ThreadPoolBuildError` if rayon fails to build the pool.
+    */
+    pub fn with_num_threads(self, num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
+        Ok(self.with_pool(Arc::new(pool)))
+    }
+
+    /// Runs `op` on this config's pool, or directly on the calling thread (and so on rayon's global pool) if there is none
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    /**
+    Returns the mode a call marking `progressions` arithmetic progressions with roughly `work` writes in total will run in.
+    `Auto` stays sequential below `parallel_threshold`, chunks a lone progression, and segments several.
+    */
+    #[must_use]
+    pub const fn resolve(&self, work: usize, progressions: usize) -> MarkingMode {
+        match self.mode {
+            MarkingMode::Auto if work < self.parallel_threshold => MarkingMode::Sequential,
+            MarkingMode::Auto if progressions > 1 => MarkingMode::Segmented,
+            MarkingMode::Auto => MarkingMode::Chunked,
+            mode => mode,
+        }
+    }
+}
+
+/// A validated `(start, stop, step_size)` progression of indices to set to false.
+type Progression = (usize, usize, usize);
+
+impl SieveVecBool {
+    /**
+    Sets every index of every progression to false using the mode `config` resolves to, on `config`'s pool, and returns that mode.
+    Every progression must already be known to only contain valid indices.
+    */
+    fn mark_progressions(
+        &mut self,
+        progressions: &[Progression],
+        config: &SieveConfig,
+    ) -> MarkingMode {
+        let work = progressions
+            .iter()
+            .map(|&(start, stop, step_size)| stop.saturating_sub(start) / step_size)
+            .fold(0, usize::saturating_add);
+        let mode = config.resolve(work, progressions.len());
+        let segment_len = config.segment_len.max(1);
+        config.install(|| self.run_progressions(progressions, mode, segment_len));
+        mode
+    }
+
+    /// Does the marking for `mark_progressions` in the already-resolved `mode`, on whichever pool it is called from
+    fn run_progressions(
+        &mut self,
+        progressions: &[Progression],
+        mode: MarkingMode,
+        segment_len: usize,
+    ) {
+        match mode {
+            MarkingMode::Auto | MarkingMode::Sequential => {
+                for &(start, stop, step_size) in progressions {
+                    unsafe { self.set_step_range_to_false(start, stop, step_size) };
+                }
+            }
+            MarkingMode::Chunked => {
+                for &(start, stop, step_size) in progressions {
+                    unsafe { self.set_step_range_to_false_par(start, stop, step_size) };
+                }
+            }
+            MarkingMode::Segmented => {
+                self.vec.par_chunks_mut(segment_len).enumerate().for_each(
+                    |(segment_index, segment)| {
+                        let offset = segment_index * segment_len;
+                        for &(start, stop, step_size) in progressions {
+                            let stop = stop.min(offset + segment.len());
+                            for index in progression_within(start, step_size, offset, stop) {
+                                segment[index - offset] = false;
+                            }
+                        }
+                    },
+                );
+            }
+        }
+    }
+
+    /**
+    Adaptive counterpart of `set_step_range_to_false_checked`, which picks how to run from `config`.
+    Returns the mode that was used.
+
+    # Errors
+    Same as `set_step_range_to_false_checked`.
+    */
+    pub fn set_step_range_to_false_adaptive(
+        &mut self,
+        start: usize,
+        stop: usize,
+        step_size: usize,
+        config: &SieveConfig,
+    ) -> Result<MarkingMode, SieveError> {
+        validate_step_range(start, stop, step_size, self.len())?;
+        Ok(self.mark_progressions(&[(start, stop, step_size)], config))
+    }
+
+    /**
+    Adaptive counterpart of `set_multiples_to_false_checked`, which picks how to run from `config`.
+    Returns the mode that was used.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false_adaptive(
+        &mut self,
+        n: usize,
+        config: &SieveConfig,
+    ) -> Result<MarkingMode, SieveError> {
+        self.set_multiples_of_slice_to_false_adaptive(&[n], config)
+    }
+
+    /**
+    Adaptive counterpart of `set_multiples_of_slice_to_false_par_checked`, which picks how to run from `config`.
+    Returns the mode that was used.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `slice` contains a zero.
+    */
+    pub fn set_multiples_of_slice_to_false_adaptive(
+        &mut self,
+        slice: &[usize],
+        config: &SieveConfig,
+    ) -> Result<MarkingMode, SieveError> {
+        if slice.contains(&0) {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        let progressions: Vec<Progression> = slice.iter().map(|&n| (n.min(len), len, n)).collect();
+        Ok(self.mark_progressions(&progressions, config))
+    }
+
+    /**
+    Crosses off every prime in `primes` (see `cross_off_prime`), picking how to run from `config`.
+    Returns the mode that was used.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `primes` contains a zero.
+    */
+    pub fn cross_off_primes_adaptive(
+        &mut self,
+        primes: &[usize],
+        config: &SieveConfig,
+    ) -> Result<MarkingMode, SieveError> {
+        if primes.contains(&0) {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        let progressions: Vec<Progression> = primes
+            .iter()
+            .map(|&p| (p.saturating_mul(p).min(len), len, p))
+            .collect();
+        Ok(self.mark_progressions(&progressions, config))
+    }
+}
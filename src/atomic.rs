@@ -0,0 +1,207 @@
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::error::validate_step_range;
+use crate::{SieveError, SieveVecBit, WORD_BITS};
+
+/**
+A bit-packed sieve whose words are `AtomicU64`s, so it can be marked from many threads at once through `&self`.
+Elements default to true and once set to false remain false forever.
+
+Clearing a bit is a relaxed `fetch_and`: every write only ever clears bits, so the order in which threads' writes land
+doesn't matter, and rayon's join at the end of each parallel call makes them all visible to the caller.
+Unlike writing `false` through a `ThreadSafeMutPtr`, concurrent marking here is free of data races under the Rust memory model,
+so every method is safe and takes `&self`.
+*/
+#[derive(Debug, Default)]
+pub struct AtomicSieveVecBit {
+    words: Vec<AtomicU64>,
+    len: usize,
+}
+
+impl From<SieveVecBit> for AtomicSieveVecBit {
+    fn from(sieve: SieveVecBit) -> Self {
+        let len = sieve.len();
+        Self {
+            words: sieve.into_inner().into_iter().map(AtomicU64::new).collect(),
+            len,
+        }
+    }
+}
+
+impl AtomicSieveVecBit {
+    /// Returns an empty `AtomicSieveVecBit`
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns an `AtomicSieveVecBit` of length `len` with every element set to true
+    #[must_use]
+    pub fn with_len(len: usize) -> Self {
+        SieveVecBit::with_len(len).into()
+    }
+
+    /// Returns the marked bits as a plain `SieveVecBit`
+    #[must_use]
+    pub fn into_sieve_vec_bit(self) -> SieveVecBit {
+        let words = self.words.into_iter().map(AtomicU64::into_inner).collect();
+        SieveVecBit::from_words(words, self.len)
+    }
+
+    /// Returns the number of elements
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no elements
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of range
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| {
+            self.words[index / WORD_BITS].load(Ordering::Relaxed) & (1 << (index % WORD_BITS)) != 0
+        })
+    }
+
+    /**
+    Returns the number of elements still set to true.
+    Each word is read atomically, but words cleared by other threads while counting may or may not be reflected.
+    */
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_ones() as usize)
+            .sum()
+    }
+
+    /// Clears the bit at `index`, which must already be known to be in range
+    fn clear(&self, index: usize) {
+        self.words[index / WORD_BITS].fetch_and(!(1 << (index % WORD_BITS)), Ordering::Relaxed);
+    }
+
+    /**
+    Sets the element at `index` to false.
+    # Errors
+    Returns `SieveError::OutOfRange` if `index` is not less than `self.len()`.
+    */
+    pub fn set_false(&self, index: usize) -> Result<(), SieveError> {
+        if index >= self.len {
+            return Err(SieveError::OutOfRange {
+                index,
+                len: self.len,
+            });
+        }
+        self.clear(index);
+        Ok(())
+    }
+
+    /**
+    Sets all the indices in `(start..stop).step_by(step_size)` to false.
+    # Errors
+    - `SieveError::ZeroStep` if `step_size` is zero.
+    - `SieveError::StartAfterStop` if `start > stop`.
+    - `SieveError::OutOfRange` if any index in the range is not a valid index.
+    */
+    pub fn set_step_range_to_false(
+        &self,
+        start: usize,
+        stop: usize,
+        step_size: usize,
+    ) -> Result<(), SieveError> {
+        validate_step_range(start, stop, step_size, self.len)?;
+        (start..stop)
+            .step_by(step_size)
+            .for_each(|index| self.clear(index));
+        Ok(())
+    }
+
+    /**
+    Parallel version of `set_step_range_to_false`.
+    The progression is split by position, so each rayon task computes its own indices directly from `start` and `step_size`.
+
+    # Errors
+    Same as `set_step_range_to_false`.
+    */
+    pub fn set_step_range_to_false_par(
+        &self,
+        start: usize,
+        stop: usize,
+        step_size: usize,
+    ) -> Result<(), SieveError> {
+        validate_step_range(start, stop, step_size, self.len)?;
+        let count = (stop - start).div_ceil(step_size);
+        (0..count)
+            .into_par_iter()
+            .for_each(|k| self.clear(start + k * step_size));
+        Ok(())
+    }
+
+    /**
+    Sets the multiples of `n` to false, starting from `n` itself.
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false(&self, n: usize) -> Result<(), SieveError> {
+        self.set_step_range_to_false(n.min(self.len), self.len, n)
+    }
+
+    /**
+    Parallel version of `set_multiples_to_false`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false_par(&self, n: usize) -> Result<(), SieveError> {
+        self.set_step_range_to_false_par(n.min(self.len), self.len, n)
+    }
+
+    /**
+    Calls `set_multiples_to_false` for every element of `slice`, in parallel over `slice`.
+    Every element is validated before anything is written.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `slice` contains a zero.
+    */
+    pub fn set_multiples_of_slice_to_false_par(&self, slice: &[usize]) -> Result<(), SieveError> {
+        if slice.contains(&0) {
+            return Err(SieveError::ZeroStep);
+        }
+        slice.par_iter().for_each(|&n| {
+            (n..self.len).step_by(n).for_each(|index| self.clear(index));
+        });
+        Ok(())
+    }
+
+    /**
+    Crosses off the prime `p`: every multiple of `p` from `p * p` onwards is set to false.
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime(&self, p: usize) -> Result<(), SieveError> {
+        let start = p.saturating_mul(p).min(self.len);
+        self.set_step_range_to_false(start, self.len, p)
+    }
+
+    /**
+    Parallel version of `cross_off_prime`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime_par(&self, p: usize) -> Result<(), SieveError> {
+        let start = p.saturating_mul(p).min(self.len);
+        self.set_step_range_to_false_par(start, self.len, p)
+    }
+}
@@ -0,0 +1,540 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::primes::sieve_up_to;
+use crate::segmented::{DEFAULT_SEGMENT_LEN, sieve_segment};
+use crate::{
+    RankSelect, SieveConfig, SieveVecBool, SpfSieve, WORD_BITS, WORDS_PER_BLOCK, mobius, to_index,
+};
+
+/// Number of leading primes whose φ values are answered from a precomputed table.
+const PHI_TABLE_PRIMES: usize = 6;
+
+/// Arguments below which `prime_count` simply sieves, as Lagarias–Miller–Odlyzko has nothing to gain there.
+const DIRECT_LIMIT: u64 = 1 << 20;
+
+/**
+Returns π(x), the number of primes less than or equal to `x`.
+
+Uses the Lagarias–Miller–Odlyzko method, with Deléglise and Rivat's shortcut for the easy special leaves.
+With `y = α·cbrt(x)` and `a = π(y)`, π(x) = φ(x, a) + a - 1 - P2(x, a), and the Legendre sum φ(x, a) is split into
+ordinary leaves, answered from a small φ table, and special leaves. Those whose prime is above `x^(1/4)` are answered
+from a `RankSelect` prime-count table up to `sqrt(x)`, which is sieved with `SieveVecBool`; the rest come from a
+segmented sieve up to `x / y` that keeps a running count per 512 bits. P2 is counted with a segmented `SieveVecBool` sieve.
+
+Time is roughly `O(x^(2/3))` and memory `O(sqrt(x))` bits plus `O(y)` words.
+On one core of a recent x86-64 machine π(10^12) takes about 0.25 s, π(10^14) about 4 s and π(10^15) about 17 s.
+*/
+#[must_use]
+pub fn prime_count(x: u64) -> u64 {
+    if x < DIRECT_LIMIT {
+        return sieve_up_to(to_index(x)).count_primes() as u64;
+    }
+    PrimeCounter::new(x, DEFAULT_SEGMENT_LEN).count(false)
+}
+
+/**
+Parallel version of `prime_count`.
+The sieve segments, the easy leaves and the ordinary leaves are spread across rayon tasks,
+with each segment's counts offset by those of the segments before it once they are all done.
+*/
+#[must_use]
+pub fn prime_count_par(x: u64) -> u64 {
+    prime_count_par_with(x, &SieveConfig::default())
+}
+
+/// Version of `prime_count_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn prime_count_par_with(x: u64, config: &SieveConfig) -> u64 {
+    config.install(|| {
+        if x < DIRECT_LIMIT {
+            return sieve_up_to(to_index(x)).count_primes_par() as u64;
+        }
+        PrimeCounter::new(x, config.segment_len.max(1)).count(true)
+    })
+}
+
+/// The tables shared by every step of one `prime_count` call.
+struct PrimeCounter {
+    x: u64,
+
+    /// The largest value whose primes are counted straight from the table, `isqrt(x)`.
+    root: u64,
+
+    /// The split point `α·cbrt(x)`: every leaf's multiplier `m` is at most `y`.
+    y: u64,
+
+    /// Number of values in each segment of the special-leaf and P2 sieves.
+    segment_len: u64,
+
+    /// π(y), the number of primes φ(x, a) removes.
+    a: usize,
+
+    /// The largest `b` whose special leaves come from the sieve rather than the prime-count table.
+    b_hard: usize,
+
+    /// The primes up to `root`, in ascending order.
+    primes: Vec<u64>,
+
+    /// A rank index over the prime sieve of `0..=root`, so π(n) is `pi.rank(n + 1)`.
+    pi: RankSelect,
+
+    /// `leaves[m]` is μ(m) times the smallest prime factor of `m`, and so 0 unless `m` is squarefree, for `m` up to `y`.
+    leaves: Vec<i32>,
+
+    /// The product of the first `PHI_TABLE_PRIMES` primes.
+    modulus: u64,
+
+    /// φ(modulus, `PHI_TABLE_PRIMES`), the count of residues coprime to the first `PHI_TABLE_PRIMES` primes.
+    totient: u64,
+
+    /// `phi_table[r]` is φ(r, `PHI_TABLE_PRIMES`) for `r < modulus`.
+    phi_table: Vec<u32>,
+}
+
+impl PrimeCounter {
+    /// Builds the tables for counting the primes up to `x`, which must be at least `DIRECT_LIMIT`, sieving `segment_len` values at a time
+    fn new(x: u64, segment_len: usize) -> Self {
+        let root = x.isqrt();
+        let cbrt = integer_root(x, 3);
+        let y = cbrt.saturating_mul(alpha(x)).clamp(cbrt, root);
+
+        let sieve = sieve_up_to(to_index(root));
+        let primes: Vec<u64> = (0..)
+            .zip(sieve.vec.iter())
+            .filter_map(|(n, &is_prime)| is_prime.then_some(n))
+            .collect();
+        let pi = RankSelect::from(sieve);
+
+        let spf = SpfSieve::new(u32::try_from(y).unwrap_or(u32::MAX)).into_inner();
+        let leaves = mobius(to_index(y))
+            .into_iter()
+            .zip(spf)
+            .map(|(mu, spf)| i32::from(mu) * i32::try_from(spf).unwrap_or(i32::MAX))
+            .collect();
+
+        let small = &primes[..PHI_TABLE_PRIMES];
+        let modulus: u64 = small.iter().product();
+        let totient = small.iter().map(|&p| p - 1).product();
+        let phi_table = (0..modulus)
+            .scan(0, |count, r| {
+                if r > 0 && small.iter().all(|&p| !r.is_multiple_of(p)) {
+                    *count += 1;
+                }
+                Some(*count)
+            })
+            .collect();
+
+        let mut counter = Self {
+            x,
+            root,
+            y,
+            segment_len: segment_len as u64,
+            a: 0,
+            b_hard: 0,
+            primes,
+            pi,
+            leaves,
+            modulus,
+            totient,
+            phi_table,
+        };
+        counter.a = counter.pi(y);
+        counter.b_hard = counter.pi(integer_root(x, 4)).max(PHI_TABLE_PRIMES);
+        counter
+    }
+
+    /// Returns the `b`th prime, counting from 1
+    fn prime(&self, b: usize) -> u64 {
+        self.primes[b - 1]
+    }
+
+    /// Returns π(n) for `n` up to `self.root`
+    fn pi(&self, n: u64) -> usize {
+        self.pi.rank(to_index(n) + 1)
+    }
+
+    /// Returns φ(n, `PHI_TABLE_PRIMES`), the number of integers in `1..=n` not divisible by any of the first `PHI_TABLE_PRIMES` primes
+    fn phi_small(&self, n: u64) -> u64 {
+        n / self.modulus * self.totient + u64::from(self.phi_table[to_index(n % self.modulus)])
+    }
+
+    /// Returns π(x) as φ(x, a) + a - 1 - P2(x, a), spreading the work across rayon tasks if `parallel`
+    fn count(&self, parallel: bool) -> u64 {
+        let phi = self.ordinary_leaves(parallel)
+            + self.hard_leaves(parallel)
+            + self.easy_leaves(parallel);
+        let count = phi + self.a as i128 - 1 - self.p2(parallel);
+        debug_assert!(count >= 0, "π({}) came out negative: {count}", self.x);
+        u64::try_from(count).unwrap_or(0)
+    }
+
+    /**
+    Returns the sum of the ordinary leaves μ(n) φ(x / n, c), over the squarefree `n` up to `y`
+    whose prime factors all lie above the first `c = PHI_TABLE_PRIMES` primes.
+    */
+    fn ordinary_leaves(&self, parallel: bool) -> i128 {
+        let largest_small = self.prime(PHI_TABLE_PRIMES);
+        let leaf = |n: usize| {
+            let key = self.leaves[n];
+            if u64::from(key.unsigned_abs()) > largest_small {
+                i128::from(key.signum()) * i128::from(self.phi_small(self.x / n as u64))
+            } else {
+                0
+            }
+        };
+        let y = to_index(self.y);
+        let leaves: i128 = if parallel {
+            (2..=y).into_par_iter().map(leaf).sum()
+        } else {
+            (2..=y).map(leaf).sum()
+        };
+        i128::from(self.phi_small(self.x)) + leaves
+    }
+
+    /**
+    Returns the sum of the special leaves `-μ(m) φ(x / (p_b m), b - 1)` for `c < b <= b_hard`,
+    where `m <= y < p_b m` and every prime factor of `m` is above `p_b`.
+
+    The sieve over `0..x / y` is split into segments, each crossing off the primes in order and answering
+    a leaf with prime `p_b` just before crossing off `p_b` itself. A segment only sees its own values, so it reports
+    its leaves' counts within itself, the weight of each `b`'s leaves, and each `b`'s survivors; adding
+    weight times the survivors of all earlier segments then completes every leaf.
+    */
+    fn hard_leaves(&self, parallel: bool) -> i128 {
+        if self.b_hard <= PHI_TABLE_PRIMES {
+            return 0;
+        }
+        let segments = self.leaf_limit().div_ceil(self.segment_len);
+        let mut before = vec![0; self.b_hard + 1];
+        let mut sum = 0;
+        let mut combine = |segment: SegmentLeaves| {
+            sum += i128::from(segment.partial);
+            let leaves = segment
+                .weights
+                .iter()
+                .zip(&segment.survivors)
+                .zip(&mut before);
+            for ((&weight, &survivors), before) in leaves.skip(PHI_TABLE_PRIMES + 1) {
+                sum += i128::from(weight) * i128::from(*before);
+                *before += survivors;
+            }
+        };
+        if parallel {
+            let segments: Vec<SegmentLeaves> = (0..segments)
+                .into_par_iter()
+                .map_init(LeafSieve::default, |sieve, index| {
+                    self.sieve_leaves(index, sieve)
+                })
+                .collect();
+            segments.into_iter().for_each(combine);
+        } else {
+            let mut sieve = LeafSieve::default();
+            for index in 0..segments {
+                combine(self.sieve_leaves(index, &mut sieve));
+            }
+        }
+        sum
+    }
+
+    /// Returns one past the largest value a special leaf can take, `x / (y + 1)`
+    const fn leaf_limit(&self) -> u64 {
+        self.x / (self.y + 1) + 1
+    }
+
+    /// Sieves the `index`th segment of the special-leaf sieve in `sieve`, returning what its leaves contribute
+    fn sieve_leaves(&self, index: u64, sieve: &mut LeafSieve) -> SegmentLeaves {
+        let low = index * self.segment_len;
+        let high = (low + self.segment_len).min(self.leaf_limit());
+        let mut leaves = SegmentLeaves::new(self.b_hard);
+        sieve.reset(low, high);
+        for b in 1..=PHI_TABLE_PRIMES {
+            sieve.cross_off(self.prime(b));
+        }
+        let sqrt_y = self.y.isqrt();
+        for b in PHI_TABLE_PRIMES + 1..=self.b_hard {
+            let p = self.prime(b);
+            let xp = self.x / p;
+            // Above sqrt(y) every `m` is a prime above `p`, so no leaf reaches `x / p^2`, nor does any later prime's.
+            if p > sqrt_y && xp / p <= low {
+                break;
+            }
+            let max_m = xp.checked_div(low).map_or(self.y, |m| m.min(self.y));
+            let min_m = (xp / high).max(self.y / p).max(p);
+            let mut cursor = BlockCursor::default();
+            if max_m > min_m && p <= sqrt_y {
+                for m in (min_m + 1..=max_m).rev() {
+                    let key = self.leaves[to_index(m)];
+                    if u64::from(key.unsigned_abs()) > p {
+                        let mu = i64::from(key.signum());
+                        leaves.partial -= mu * sieve.count_up_to(&mut cursor, xp / m);
+                        leaves.weights[b] -= mu;
+                    }
+                }
+            } else if max_m > min_m {
+                for &q in self.primes[self.pi(min_m)..self.pi(max_m)].iter().rev() {
+                    leaves.partial += sieve.count_up_to(&mut cursor, xp / q);
+                    leaves.weights[b] += 1;
+                }
+            }
+            leaves.survivors[b] = sieve.remaining;
+            sieve.cross_off(p);
+        }
+        leaves
+    }
+
+    /**
+    Returns the sum of the special leaves for `b_hard < b <= a`, where `p_b > x^(1/4)`.
+    Every such leaf is `φ(x / (p_b q), b - 1)` for a prime `q` above `p_b`, and its argument is below both `p_b^2`
+    and `sqrt(x)`, so it is 1 if the argument is below `p_b` and `π(x / (p_b q)) - b + 2` from the table otherwise.
+    */
+    fn easy_leaves(&self, parallel: bool) -> i128 {
+        let leaves = self.b_hard + 1..=self.a;
+        if parallel {
+            leaves.into_par_iter().map(|b| self.easy_leaves_of(b)).sum()
+        } else {
+            leaves.map(|b| self.easy_leaves_of(b)).sum()
+        }
+    }
+
+    /**
+    Returns the sum of the special leaves with prime `p_b`, for `b > b_hard`.
+    Runs of consecutive `q` that share a value of `π(x / (p_b q))` are added in one step.
+    */
+    fn easy_leaves_of(&self, b: usize) -> i128 {
+        let p = self.prime(b);
+        let xp = self.x / p;
+        let low = p.max(self.y / p);
+        if low >= self.y {
+            return 0;
+        }
+        // Leaves with `q` past `x / p^2` have an argument below `p` and so are 1.
+        let split = (xp / p).clamp(low, self.y);
+        let mut sum = (self.pi(self.y) - self.pi(split)) as i128;
+        let mut i = self.pi(low);
+        let end = self.pi(split);
+        while i < end {
+            let count = self.pi(xp / self.primes[i]);
+            let j = self.pi(xp / self.prime(count)).min(end);
+            sum += ((count + 2 - b) * (j - i)) as i128;
+            i = j;
+        }
+        sum
+    }
+
+    /**
+    Returns P2(x, a), the number of `n <= x` with exactly two prime factors above `p_a`, as the sum of
+    `π(x / p_b) - (b - 1)` over the primes `y < p_b <= sqrt(x)`.
+    The arguments `x / p_b` past `sqrt(x)` are counted by a segmented `SieveVecBool` sieve running from `sqrt(x)` up to `x / y`.
+    */
+    fn p2(&self, parallel: bool) -> i128 {
+        let b_max = self.primes.len();
+        if self.a >= b_max {
+            return 0;
+        }
+        let x = self.x;
+        let low = self.root + 1;
+        let high = x / self.prime(self.a + 1) + 1;
+        let base_primes = &self.primes[..self.pi((high - 1).isqrt())];
+        let segments = high.saturating_sub(low).div_ceil(self.segment_len);
+        let segment = |sieve: &mut SieveVecBool, index: u64| {
+            let segment_low = low + index * self.segment_len;
+            let segment_high = (segment_low + self.segment_len).min(high);
+            self.p2_segment(sieve, segment_low, segment_high, base_primes)
+        };
+        let counts: Vec<P2Segment> = if parallel {
+            (0..segments)
+                .into_par_iter()
+                .map_init(SieveVecBool::new, segment)
+                .collect()
+        } else {
+            let mut sieve = SieveVecBool::new();
+            (0..segments)
+                .map(|index| segment(&mut sieve, index))
+                .collect()
+        };
+
+        // The arguments no larger than `sqrt(x)` come straight from the table.
+        let mut sum: i128 = (self.pi(x / low).max(self.a) + 1..=b_max)
+            .map(|b| self.pi(x / self.prime(b)) as i128)
+            .sum();
+        let mut below = self.pi(self.root) as u64;
+        for counts in counts {
+            sum += i128::from(counts.partial) + i128::from(counts.arguments) * i128::from(below);
+            below += counts.primes;
+        }
+        let (a, b_max) = (self.a as i128, b_max as i128);
+        sum - (a + b_max - 1) * (b_max - a) / 2
+    }
+
+    /// Sieves `low..high` in `sieve` and counts the primes up to every `x / p_b` in it, for P2
+    fn p2_segment(
+        &self,
+        sieve: &mut SieveVecBool,
+        low: u64,
+        high: u64,
+        base_primes: &[u64],
+    ) -> P2Segment {
+        sieve.reset(to_index(high - low));
+        sieve_segment(sieve, low, base_primes);
+        let mut counts = P2Segment::default();
+        let first = self.pi(self.x / high).max(self.a) + 1;
+        let last = self.pi(self.x / low).min(self.primes.len());
+        let (mut position, mut count) = (0, 0);
+        for b in (first..=last).rev() {
+            let end = to_index(self.x / self.prime(b) - low) + 1;
+            count += sieve.vec[position..end]
+                .iter()
+                .filter(|&&is_prime| is_prime)
+                .count() as u64;
+            position = end;
+            counts.partial += count;
+            counts.arguments += 1;
+        }
+        counts.primes = count
+            + sieve.vec[position..]
+                .iter()
+                .filter(|&&is_prime| is_prime)
+                .count() as u64;
+        counts
+    }
+}
+
+/// What one segment of the special-leaf sieve contributes, before the survivors of earlier segments are added in.
+struct SegmentLeaves {
+    /// The sum of -μ(m) times the number of survivors in the segment up to each leaf's argument.
+    partial: i64,
+
+    /// `weights[b]` is the sum of -μ(m) over the leaves with prime `p_b` in the segment.
+    weights: Vec<i64>,
+
+    /// `survivors[b]` is the number of values in the segment not divisible by any of the first `b - 1` primes.
+    survivors: Vec<u64>,
+}
+
+impl SegmentLeaves {
+    /// Returns an empty `SegmentLeaves` for the primes up to `p_b_hard`
+    fn new(b_hard: usize) -> Self {
+        Self {
+            partial: 0,
+            weights: vec![0; b_hard + 1],
+            survivors: vec![0; b_hard + 1],
+        }
+    }
+}
+
+/// What one segment of the P2 sieve contributes, before the primes of earlier segments are added in.
+#[derive(Debug, Default, Clone, Copy)]
+struct P2Segment {
+    /// The sum of the number of primes in the segment up to each argument `x / p_b` it holds.
+    partial: u64,
+
+    /// The number of arguments `x / p_b` the segment holds.
+    arguments: u64,
+
+    /// The number of primes in the segment.
+    primes: u64,
+}
+
+/**
+One segment of the special-leaf sieve: a bit per value, set while no prime crossed off yet divides it,
+with a running count per block so counting up to any value takes one pass over the blocks plus a few popcounts.
+*/
+#[derive(Debug, Default, Clone)]
+struct LeafSieve {
+    low: u64,
+    high: u64,
+    words: Vec<u64>,
+
+    /// `blocks[k]` is the number of set bits in words `k * WORDS_PER_BLOCK..(k + 1) * WORDS_PER_BLOCK`.
+    blocks: Vec<u32>,
+
+    /// The number of set bits in the whole segment.
+    remaining: u64,
+}
+
+/// A position in a `LeafSieve`'s blocks, advanced as the values counted up to increase.
+#[derive(Debug, Default, Clone, Copy)]
+struct BlockCursor {
+    block: usize,
+    count: u64,
+}
+
+impl LeafSieve {
+    /// Sets every value of `low..high` except 0, reusing the existing buffers
+    fn reset(&mut self, low: u64, high: u64) {
+        let len = to_index(high - low);
+        self.low = low;
+        self.high = high;
+        self.words.clear();
+        self.words.resize(len.div_ceil(WORD_BITS), u64::MAX);
+        if let Some(last) = self.words.last_mut()
+            && !len.is_multiple_of(WORD_BITS)
+        {
+            *last = (1 << (len % WORD_BITS)) - 1;
+        }
+        if low == 0 {
+            self.words[0] &= !1;
+        }
+        self.blocks.clear();
+        self.blocks.extend(
+            self.words
+                .chunks(WORDS_PER_BLOCK)
+                .map(|block| block.iter().map(|word| word.count_ones()).sum::<u32>()),
+        );
+        self.remaining = self.blocks.iter().map(|&count| u64::from(count)).sum();
+    }
+
+    /// Clears every multiple of `p` in the segment, `p` itself included
+    fn cross_off(&mut self, p: u64) {
+        let first = self.low.div_ceil(p).max(1) * p;
+        for value in (first..self.high).step_by(to_index(p)) {
+            let offset = to_index(value - self.low);
+            let word = &mut self.words[offset / WORD_BITS];
+            let bit = 1 << (offset % WORD_BITS);
+            let was_set = u32::from(*word & bit != 0);
+            *word &= !bit;
+            self.blocks[offset / (WORD_BITS * WORDS_PER_BLOCK)] -= was_set;
+            self.remaining -= u64::from(was_set);
+        }
+    }
+
+    /// Returns the number of set values from the start of the segment up to and including `value`, which must not decrease between calls sharing `cursor`
+    fn count_up_to(&self, cursor: &mut BlockCursor, value: u64) -> i64 {
+        let offset = to_index(value - self.low);
+        let block = offset / (WORD_BITS * WORDS_PER_BLOCK);
+        while cursor.block < block {
+            cursor.count += u64::from(self.blocks[cursor.block]);
+            cursor.block += 1;
+        }
+        let word = offset / WORD_BITS;
+        let whole_words: u32 = self.words[block * WORDS_PER_BLOCK..word]
+            .iter()
+            .map(|word| word.count_ones())
+            .sum();
+        let partial = self.words[word] & (u64::MAX >> (WORD_BITS - 1 - offset % WORD_BITS));
+        i64::try_from(cursor.count + u64::from(whole_words + partial.count_ones()))
+            .unwrap_or(i64::MAX)
+    }
+}
+
+/// Returns the `α` in `y = α·cbrt(x)`, trading the special-leaf sieve up to `x / y` against the `O(y)` tables
+const fn alpha(x: u64) -> u64 {
+    // Measured to be close to the best choice from 10^12 up to 10^15.
+    (x.ilog2() / 2) as u64
+}
+
+/// Returns the largest `r` with `r^k <= x`, for `k >= 2`
+fn integer_root(x: u64, k: u32) -> u64 {
+    let fits = |r: u64| r.checked_pow(k).is_some_and(|power| power <= x);
+    // Binary search below `isqrt(x)`, which is at least the root for every `k >= 2`.
+    let (mut low, mut high) = (0, x.isqrt());
+    while low < high {
+        let mid = high - (high - low) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
@@ -0,0 +1,127 @@
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(debug_assertions)]
+use std::sync::{Mutex, PoisonError};
+
+use crate::ScopedMutPtr;
+
+/**
+Lets parallel tasks write to disjoint indices of a borrowed `&'a mut [T]`, packaging the crate's
+`ptr.add(index).write(value)` trick behind one type.
+
+Each task writes through its own `DisjointTask`, handed out by `task`, e.g. as the init value of rayon's `for_each_init`.
+In debug builds every write is recorded along with the task that made it, and writing a different value
+to an index another task has already written panics, which catches overlapping work split across tasks
+even when rayon runs those tasks on the same thread.
+In release builds none of this bookkeeping exists and `write` compiles down to a plain pointer write.
+*/
+#[derive(Debug)]
+pub struct DisjointWriter<'a, T> {
+    ptr: ScopedMutPtr<'a, T>,
+    #[cfg(debug_assertions)]
+    next_task: AtomicUsize,
+    /// Every distinct `(task, value)` pair written to each index so far.
+    #[cfg(debug_assertions)]
+    writes: Mutex<HashMap<usize, Vec<(usize, T)>>>,
+}
+
+/// One task's handle onto a `DisjointWriter`, whose writes are checked against every other task's in debug builds.
+#[derive(Debug)]
+pub struct DisjointTask<'w, 'a, T> {
+    writer: &'w DisjointWriter<'a, T>,
+    #[cfg(debug_assertions)]
+    id: usize,
+}
+
+impl<'a, T: Copy + PartialEq> DisjointWriter<'a, T> {
+    /// Returns a new `DisjointWriter` over `slice`, which stays mutably borrowed for `'a`
+    #[must_use]
+    pub fn new(slice: &'a mut [T]) -> Self {
+        Self {
+            ptr: ScopedMutPtr::new(slice),
+            #[cfg(debug_assertions)]
+            next_task: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            writes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the length of the borrowed slice
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.ptr.len()
+    }
+
+    /// Returns true if the borrowed slice is empty
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.ptr.is_empty()
+    }
+
+    /// Returns a handle for a new task, distinct from every task handed out before it
+    #[must_use]
+    pub fn task(&self) -> DisjointTask<'_, 'a, T> {
+        DisjointTask {
+            writer: self,
+            #[cfg(debug_assertions)]
+            id: self.next_task.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Records a write to `index` by `task`, panicking if it conflicts with any write made by another task
+    #[cfg(debug_assertions)]
+    fn record(&self, task: usize, index: usize, value: T) {
+        assert!(
+            index < self.len(),
+            "index {index} is out of range for a slice of length {}",
+            self.len()
+        );
+        let mut writes = self.writes.lock().unwrap_or_else(PoisonError::into_inner);
+        let previous = writes.entry(index).or_default();
+        let conflict = previous
+            .iter()
+            .find(|&&(previous_task, previous)| previous_task != task && previous != value)
+            .map(|&(previous_task, _)| previous_task);
+        if !previous.contains(&(task, value)) {
+            previous.push((task, value));
+        }
+        drop(writes);
+        if let Some(previous_task) = conflict {
+            panic!(
+                "index {index} was written with different values by tasks {previous_task} and {task}"
+            );
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> DisjointTask<'_, '_, T> {
+    /// Returns the length of the borrowed slice
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.writer.len()
+    }
+
+    /// Returns true if the borrowed slice is empty
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.writer.is_empty()
+    }
+
+    /**
+    Replaces the element at `index` with `value`.
+
+    # Panics
+    In debug builds, panics if `index` is out of range or another task has already written a different value to `index`.
+
+    # Safety
+    - `index` must be less than `self.len()`
+    - No other task may access the element at `index` at the same time.
+    */
+    pub unsafe fn write(&self, index: usize, value: T) {
+        #[cfg(debug_assertions)]
+        self.writer.record(self.id, index, value);
+        unsafe { self.writer.ptr.write(index, value) };
+    }
+}
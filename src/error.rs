@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// The ways a bounds-checked marking operation can reject its arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SieveError {
+    /// `index` is not a valid index into a sieve of length `len`.
+    OutOfRange { index: usize, len: usize },
+
+    /// A step size of zero was given, which would never make progress.
+    ZeroStep,
+
+    /// The range given starts after it stops.
+    StartAfterStop { start: usize, stop: usize },
+
+    /// `value` is not stored in a sieve over the values below `limit`, either because it is too large or because the sieve skips it.
+    NotStored { value: usize, limit: usize },
+}
+
+impl fmt::Display for SieveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { index, len } => {
+                write!(
+                    f,
+                    "index {index} is out of range for a sieve of length {len}"
+                )
+            }
+            Self::ZeroStep => write!(f, "step size must be non-zero"),
+            Self::StartAfterStop { start, stop } => {
+                write!(f, "range start {start} is greater than its stop {stop}")
+            }
+            Self::NotStored { value, limit } => {
+                write!(
+                    f,
+                    "value {value} is not stored in a sieve over the values below {limit}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SieveError {}
+
+/**
+Checks that every element of `(start..stop).step_by(step_size)` is a valid index into a sieve of length `len`.
+Returns the last index the range would touch, or `None` if the range is empty.
+*/
+pub const fn validate_step_range(
+    start: usize,
+    stop: usize,
+    step_size: usize,
+    len: usize,
+) -> Result<Option<usize>, SieveError> {
+    if step_size == 0 {
+        return Err(SieveError::ZeroStep);
+    }
+    if start > stop {
+        return Err(SieveError::StartAfterStop { start, stop });
+    }
+    if start == stop {
+        return Ok(None);
+    }
+    let last = start + (stop - 1 - start) / step_size * step_size;
+    if last >= len {
+        return Err(SieveError::OutOfRange { index: last, len });
+    }
+    Ok(Some(last))
+}
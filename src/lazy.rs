@@ -0,0 +1,94 @@
+use crate::SieveVecBool;
+use crate::primes::small_primes;
+use crate::segmented::{DEFAULT_SEGMENT_LEN, collect_segment, sieve_segment};
+
+/// Length of the first segment, kept small so taking a handful of primes stays cheap.
+const INITIAL_SEGMENT_LEN: usize = 1 << 10;
+
+/// Returns an iterator over every prime in ascending order, see `Primes`
+#[must_use]
+pub const fn primes() -> Primes {
+    Primes::new()
+}
+
+/**
+An unbounded iterator over the primes, in ascending order.
+Internally slides a `SieveVecBool` segment along the number line, doubling the segment length up to
+`DEFAULT_SEGMENT_LEN` and re-sieving more base primes whenever the next segment reaches past the square of the largest one,
+so no sieve size has to be guessed up front.
+
+Stops only if the next segment would pass `u64::MAX`.
+*/
+#[derive(Debug, Clone)]
+pub struct Primes {
+    base: Vec<u64>,
+    base_limit: u64,
+    segment: SieveVecBool,
+    segment_len: usize,
+    low: u64,
+    buffer: Vec<u64>,
+    position: usize,
+}
+
+impl Default for Primes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Primes {
+    /// Returns a `Primes` starting from 2
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            base: Vec::new(),
+            base_limit: 0,
+            segment: SieveVecBool::new(),
+            segment_len: INITIAL_SEGMENT_LEN,
+            low: 0,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Makes sure the base primes cover every prime up to `sqrt(high - 1)`, at least doubling their range when they don't
+    fn grow_base_primes(&mut self, high: u64) {
+        let needed = (high - 1).isqrt();
+        if needed <= self.base_limit {
+            return;
+        }
+        self.base_limit = needed.max(self.base_limit.saturating_mul(2));
+        let limit = usize::try_from(self.base_limit).unwrap_or(usize::MAX);
+        self.base = small_primes(limit).into_iter().map(|p| p as u64).collect();
+    }
+
+    /// Sieves the next segment into `buffer`, returning false once `u64::MAX` would be passed
+    fn sieve_next_segment(&mut self) -> bool {
+        let Some(high) = self.low.checked_add(self.segment_len as u64) else {
+            return false;
+        };
+        self.grow_base_primes(high);
+        self.segment.reset(self.segment_len);
+        sieve_segment(&mut self.segment, self.low, &self.base);
+        self.buffer = collect_segment(&self.segment, self.low);
+        self.position = 0;
+        self.low = high;
+        self.segment_len = (self.segment_len * 2).min(DEFAULT_SEGMENT_LEN);
+        true
+    }
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position == self.buffer.len() {
+            if !self.sieve_next_segment() {
+                return None;
+            }
+        }
+        let prime = self.buffer[self.position];
+        self.position += 1;
+        Some(prime)
+    }
+}
@@ -1,4 +1,55 @@
-use rayon::iter::{IntoParallelIterator, ParallelBridge, ParallelIterator};
+use std::iter::StepBy;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+
+mod adaptive;
+mod atomic;
+mod bit;
+mod count;
+mod disjoint;
+mod error;
+mod lazy;
+mod linear;
+mod mobius;
+mod multiplicative;
+mod odd;
+mod primes;
+mod rank;
+mod segmented;
+mod spf;
+mod storage;
+mod totient;
+mod wheel;
+
+pub use adaptive::{DEFAULT_PARALLEL_THRESHOLD, MarkingMode, SieveConfig};
+pub use atomic::AtomicSieveVecBit;
+pub use bit::SieveVecBit;
+pub use count::{prime_count, prime_count_par, prime_count_par_with};
+pub use disjoint::{DisjointTask, DisjointWriter};
+pub use error::SieveError;
+pub use lazy::{Primes, primes};
+pub use linear::Algorithm;
+pub use mobius::{
+    mobius, mobius_par, mobius_par_with, squarefree, squarefree_par, squarefree_par_with,
+};
+pub use multiplicative::{
+    divisor_counts, divisor_counts_par, divisor_counts_par_with, divisor_sums, divisor_sums_par,
+    divisor_sums_par_with, multiplicative_sieve, multiplicative_sieve_par,
+    multiplicative_sieve_par_with,
+};
+pub use odd::OddSieve;
+pub use primes::{primes_up_to, primes_up_to_par, primes_up_to_par_with, primes_up_to_with};
+pub use rank::RankSelect;
+pub use segmented::{DEFAULT_SEGMENT_LEN, SegmentedSieve, primes_in_range};
+pub use spf::{SpfSieve, linear_sieve};
+pub use storage::SieveStorage;
+pub use totient::{
+    SegmentedTotients, TotientValue, totients, totients_in_range, totients_par, totients_par_with,
+};
+pub use wheel::{Wheel, WheelSieve};
 
 /**
 A mutable pointer that we promise to use safely.
@@ -33,13 +84,11 @@ impl<T> ThreadSafeMutPtr<T> {
     }
 
     /**
-    Casts the inner pointer as a mutable reference
-
-    # Panics
-    If the inner pointer is null this panics
+    Casts the inner pointer as a mutable reference, or returns `None` if it is null
 
     # Safety
-
+    The caller picks `'a`, so nothing stops the reference outliving the data it points to;
+    prefer `ScopedMutPtr` when the pointer comes from a borrow.
     */
     #[must_use]
     pub const unsafe fn into_mut_ref<'a>(self) -> Option<&'a mut T> {
@@ -65,21 +114,178 @@ impl<T> ThreadSafeMutPtr<T> {
     /**
     Shorthand for `self.ptr.add(amount)`
     # Safety
-    Probably super unsafe but we don't care :3
+    Same as `pointer::add`: the offset pointer must stay within, or one past the end of, the allocation the pointer points into.
     */
     #[must_use]
     pub const unsafe fn add(self, amount: usize) -> Self {
         unsafe { Self::new(self.ptr.add(amount)) }
     }
 
-    /// Dereferences the pointer, replacing the value at that address with `new_value`;
-    pub const fn write(&mut self, new_value: T) {
+    /**
+    Dereferences the pointer, replacing the value at that address with `new_value`, without dropping the old one.
+    # Safety
+    - The pointer must be non-null and valid for writes.
+    - No other thread may access the value at that address at the same time.
+    */
+    pub const unsafe fn write(&mut self, new_value: T) {
         unsafe { self.ptr.write(new_value) };
     }
 }
 
-unsafe impl<T> Send for ThreadSafeMutPtr<T> {}
-unsafe impl<T> Sync for ThreadSafeMutPtr<T> {}
+// Handing the pointer to another thread hands it the ability to mutate a `T` there,
+// so `T` itself must be allowed to move between threads; `Rc` and friends are rejected at compile time.
+unsafe impl<T: Send> Send for ThreadSafeMutPtr<T> {}
+unsafe impl<T: Send> Sync for ThreadSafeMutPtr<T> {}
+
+/**
+A `ThreadSafeMutPtr` tied to a borrowed `&'a mut [T]`.
+It can be copied into as many threads as you like, but every reference it produces lives at most `'a`,
+so the borrow checker rejects any attempt to keep one after the slice is given back.
+
+# Safety
+Creating a `ScopedMutPtr` is safe; reading or writing through it is not.
+No two threads may access the same index at the same time unless all of them are only reading.
+
+# Compile errors
+References can't escape the borrow:
+```compile_fail
+let mut vec = vec![1, 2, 3];
+let element = unsafe { sieves::ScopedMutPtr::new(&mut vec).get_unchecked_mut(0) };
+drop(vec);
+*element = 4;
+```
+and non-`Send` payloads can't be shared between threads:
+```compile_fail
+let mut vec = vec![std::rc::Rc::new(1)];
+let ptr = sieves::ScopedMutPtr::new(&mut vec);
+std::thread::scope(|s| {
+    s.spawn(move || ptr.len());
+});
+```
+*/
+#[derive(Debug)]
+pub struct ScopedMutPtr<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+impl<T> Clone for ScopedMutPtr<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ScopedMutPtr<'_, T> {}
+
+impl<'a, T> ScopedMutPtr<'a, T> {
+    /// Returns a new `ScopedMutPtr` over `slice`, which stays mutably borrowed for `'a`
+    #[must_use]
+    pub const fn new(slice: &'a mut [T]) -> Self {
+        Self {
+            ptr: slice.as_mut_ptr(),
+            len: slice.len(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the length of the borrowed slice
+    #[must_use]
+    pub const fn len(self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the borrowed slice is empty
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.len == 0
+    }
+
+    /**
+    Returns a mutable reference to the element at `index`, or `None` if `index` is out of range.
+    # Safety
+    No other reference to the element at `index` may be in use while the returned one is.
+    */
+    #[must_use]
+    pub const unsafe fn get_mut(self, index: usize) -> Option<&'a mut T> {
+        if index < self.len {
+            Some(unsafe { &mut *self.ptr.add(index) })
+        } else {
+            None
+        }
+    }
+
+    /**
+    Returns a mutable reference to the element at `index`.
+    # Safety
+    - `index` must be less than `self.len()`
+    - No other reference to the element at `index` may be in use while the returned one is.
+    */
+    #[must_use]
+    pub const unsafe fn get_unchecked_mut(self, index: usize) -> &'a mut T {
+        unsafe { &mut *self.ptr.add(index) }
+    }
+
+    /**
+    Replaces the element at `index` with `value`, without dropping the old one.
+    # Safety
+    - `index` must be less than `self.len()`
+    - No other thread may access the element at `index` at the same time.
+    */
+    pub const unsafe fn write(self, index: usize, value: T) {
+        unsafe { self.ptr.add(index).write(value) };
+    }
+}
+
+unsafe impl<T: Send> Send for ScopedMutPtr<'_, T> {}
+unsafe impl<T: Send> Sync for ScopedMutPtr<'_, T> {}
+
+/**
+Returns the smallest element of the progression `start, start + step_size, ...` that is at least `from`,
+or `usize::MAX` if that element does not fit in a `usize`.
+*/
+const fn first_in_progression(start: usize, step_size: usize, from: usize) -> usize {
+    if from <= start {
+        start
+    } else {
+        start.saturating_add((from - start).div_ceil(step_size).saturating_mul(step_size))
+    }
+}
+
+/**
+Returns the elements of the progression `start, start + step_size, ...` that lie in `from..stop`,
+which is the walk one segment makes over a progression in every segmented pass.
+*/
+fn progression_within(
+    start: usize,
+    step_size: usize,
+    from: usize,
+    stop: usize,
+) -> StepBy<Range<usize>> {
+    (first_in_progression(start, step_size, from)..stop).step_by(step_size)
+}
+
+/// Returns the multiples of `n` in `from..stop`, starting at `n` itself so that 0, which every `n` divides, is never included
+fn multiples_within(n: usize, from: usize, stop: usize) -> StepBy<Range<usize>> {
+    progression_within(n, n, from, stop)
+}
+
+/// Converts `n` to a `usize`, saturating on targets where it does not fit
+fn to_index(n: u64) -> usize {
+    usize::try_from(n).unwrap_or(usize::MAX)
+}
+
+/// Number of bits in one `u64` storage word.
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Number of words covered by each cumulative count of set bits (512 bits, one cache line).
+const WORDS_PER_BLOCK: usize = 8;
+
+/// Fewest progression elements each rayon task marks in `set_step_range_to_false_par`, so tiny tasks don't drown in overhead.
+const MIN_STEPS_PER_CHUNK: usize = 1 << 12;
+
+/// Number of elements each rayon task counts in `count_ones_par`.
+const COUNT_CHUNK_LEN: usize = 1 << 16;
 
 /**
 A data-race safe `Vec<bool>` where elements default to true and once set to false remain false forever.
@@ -104,19 +310,75 @@ impl SieveVecBool {
         Self { vec: Vec::new() }
     }
 
+    /// Returns a `SieveVecBool` of length `len` with every element set to true
+    #[must_use]
+    pub fn with_len(len: usize) -> Self {
+        Self {
+            vec: vec![true; len],
+        }
+    }
+
+    /**
+    Resizes the inner `Vec` to `len` and sets every element back to true, reusing the existing allocation where possible.
+    Lets one buffer be recycled across many sieving passes, e.g. one per segment.
+    */
+    pub fn reset(&mut self, len: usize) {
+        self.vec.clear();
+        self.vec.resize(len, true);
+    }
+
     /// Returns the inner `Vec`
     #[must_use]
     pub fn into_inner(self) -> Vec<bool> {
         self.vec
     }
 
+    /// Returns the number of elements in the inner `Vec`
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Returns true if the inner `Vec` has no elements
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of range
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        self.vec.get(index).copied()
+    }
+
+    /// Returns the number of elements still set to true
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.vec.iter().map(|&b| usize::from(b)).sum()
+    }
+
+    /// Parallel version of `count_ones`, which sums per-chunk counts across rayon tasks
+    #[must_use]
+    pub fn count_ones_par(&self) -> usize {
+        self.vec
+            .par_chunks(COUNT_CHUNK_LEN)
+            .map(|chunk| chunk.iter().map(|&b| usize::from(b)).sum::<usize>())
+            .sum()
+    }
+
     /**
-    Returns a `ThreadSafeMutPtr` pointing to the start of the inner `Vec`.
-    # Safety
-    Has the same safety implications as `ThreadSafeMutPtr::new`
+    Returns the number of primes in a finished sieve, i.e. the number of indices from 2 onwards that are still set.
+    0 and 1 are never counted, whether or not they have been cleared.
     */
-    unsafe fn ptr_to_start_of_vec(&mut self) -> ThreadSafeMutPtr<bool> {
-        unsafe { ThreadSafeMutPtr::new(self.vec.as_mut_ptr()) }
+    #[must_use]
+    pub fn count_primes(&self) -> usize {
+        self.count_ones() - self.vec.iter().take(2).filter(|&&b| b).count()
+    }
+
+    /// Parallel version of `count_primes`
+    #[must_use]
+    pub fn count_primes_par(&self) -> usize {
+        self.count_ones_par() - self.vec.iter().take(2).filter(|&&b| b).count()
     }
 
     /**
@@ -132,6 +394,10 @@ impl SieveVecBool {
     Calls `set_false` on all the indices in the range given its `start`, `stop`, and `step`.
     This differs from `set_step_range_to_false` by performing its operations in parallel, which could be faster depending on your use case.
 
+    The progression's `count` elements are split into contiguous chunks of `per_chunk` elements, each covering
+    `per_chunk * step_size` consecutive elements of the `Vec` starting on an element of the progression,
+    so every rayon task owns a disjoint sub-slice and marks it with a plain strided loop.
+
     # Safety
     all elements in `(start..stop).step_by(step_size)` must be valid indices into the `Vec`.
     */
@@ -141,13 +407,21 @@ impl SieveVecBool {
         stop: usize,
         step_size: usize,
     ) {
-        let ptr: ThreadSafeMutPtr<bool> = unsafe { self.ptr_to_start_of_vec() };
-
-        let range = (start..stop).step_by(step_size);
-        range.par_bridge().for_each(move |index| unsafe {
-            let mut p = ptr.add(index);
-            p.write(false);
-        });
+        let stop = stop.min(self.len());
+        if start >= stop {
+            return;
+        }
+        let count = (stop - start).div_ceil(step_size);
+        let per_chunk = (count / (rayon::current_num_threads() * 4)).max(MIN_STEPS_PER_CHUNK);
+        let chunk_len = per_chunk.saturating_mul(step_size);
+        self.vec[start..stop]
+            .par_chunks_mut(chunk_len)
+            .for_each(|chunk| {
+                chunk
+                    .iter_mut()
+                    .step_by(step_size)
+                    .for_each(|element| *element = false);
+            });
     }
 
     /**
@@ -157,15 +431,15 @@ impl SieveVecBool {
     all elements in `(start..stop).step_by(step_size)` must be valid indices into the `Vec`.
     */
     pub unsafe fn set_step_range_to_false(&mut self, start: usize, stop: usize, step_size: usize) {
-        let mut index = start;
-        while index < stop {
+        for index in (start..stop).step_by(step_size) {
             unsafe { self.set_false_unchecked(index) };
-            index += step_size;
         }
     }
 
     /**
     Produces the multiples of `n`, setting all those indices in the inner Vec to false.
+    Note that `n` itself is included; see `cross_off_prime` for the marking a prime sieve needs.
+
     # Safety
     Has the same safety implications as `set_step_range_to_false`
     */
@@ -185,21 +459,25 @@ impl SieveVecBool {
     }
 
     /**
-    Calls `self.set_multiples_to_false` for all the items in `iter`.
-    # Panics
-    Panics if a null pointer is dereferenced
+    Calls `self.set_multiples_to_false` for all the items in `slice`.
+    Rather than giving each thread an element of `slice`, each rayon task owns a disjoint run of the `Vec`
+    and marks the multiples of every element of `slice` inside it.
 
     # Safety
+    Every element of `slice` must be non-zero.
     */
     pub unsafe fn set_multiples_of_slice_to_false_par(&mut self, slice: &[usize]) {
-        let self_ptr: ThreadSafeMutPtr<Self> = unsafe { ThreadSafeMutPtr::from_mut_ref(self) };
-        slice.into_par_iter().for_each(move |&index| {
-            unsafe {
-                self_ptr
-                    .clone()
-                    .into_mut_ref_unchecked()
-                    .set_multiples_to_false(index);
-            };
-        });
+        self.vec
+            .par_chunks_mut(DEFAULT_SEGMENT_LEN)
+            .enumerate()
+            .for_each(|(chunk_index, chunk)| {
+                let offset = chunk_index * DEFAULT_SEGMENT_LEN;
+                let chunk_stop = offset + chunk.len();
+                for &n in slice {
+                    for index in multiples_within(n, offset, chunk_stop) {
+                        chunk[index - offset] = false;
+                    }
+                }
+            });
     }
 }
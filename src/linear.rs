@@ -0,0 +1,45 @@
+/// Which sieve `primes_up_to_with` and `SpfSieve::with_algorithm` run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Algorithm {
+    /// The sieve of Eratosthenes, crossing off the multiples of each prime from its square.
+    #[default]
+    Eratosthenes,
+
+    /// Euler's linear sieve, which writes every composite exactly once, at the cost of a `u32` per value.
+    Linear,
+}
+
+/**
+Runs Euler's linear sieve over `0..=n`, returning the primes and a table where each composite holds its smallest prime factor
+and everything else holds 0.
+Each composite `m` is reached exactly once, as `i * p` where `p` is its smallest prime factor, because `i` only
+multiplies primes up to its own smallest prime factor. Those primes are at most `sqrt(n)`, so always fit a `u32`.
+*/
+pub fn linear_table(n: usize) -> (Vec<u64>, Vec<u32>) {
+    let mut spf = vec![0u32; n.saturating_add(1)];
+    let mut primes = Vec::new();
+    let mut multipliers: Vec<usize> = Vec::new();
+    let root = n.isqrt();
+    for i in 2..=n {
+        let largest = match spf[i] {
+            0 => {
+                primes.push(i as u64);
+                if i <= root {
+                    multipliers.push(i);
+                }
+                i
+            }
+            p => p as usize,
+        };
+        for &p in &multipliers {
+            if p > largest {
+                break;
+            }
+            let Some(m) = i.checked_mul(p).filter(|&m| m <= n) else {
+                break;
+            };
+            spf[m] = u32::try_from(p).unwrap_or(u32::MAX);
+        }
+    }
+    (primes, spf)
+}
@@ -0,0 +1,135 @@
+use crate::primes::small_primes;
+use crate::segmented::{fill_segments, fill_segments_par};
+use crate::{SieveConfig, SieveVecBool, first_in_progression, multiples_within};
+
+/// Returns a sieve of length `n + 1` with 0, which every square divides, already cleared
+fn initial_sieve(n: usize) -> SieveVecBool {
+    let mut sieve = SieveVecBool::with_len(n.saturating_add(1));
+    if !sieve.is_empty() {
+        unsafe { sieve.set_false_unchecked(0) };
+    }
+    sieve
+}
+
+/// Returns the square of every prime up to `sqrt(n)`, the only squares a squarefree sieve up to `n` needs to cross off
+fn prime_squares(n: usize) -> Vec<usize> {
+    small_primes(n.isqrt()).into_iter().map(|p| p * p).collect()
+}
+
+/**
+Returns a sieve over `0..=n` where index `m` is set iff `m` is squarefree, i.e. divisible by no square above 1.
+Crosses off every multiple of `p * p` for each prime `p` up to `sqrt(n)` with `set_step_range_to_false`;
+any square divisor has a prime one, so that is enough. 0 is divisible by every square and is cleared.
+*/
+#[must_use]
+pub fn squarefree(n: usize) -> SieveVecBool {
+    let mut sieve = initial_sieve(n);
+    let len = sieve.len();
+    for square in prime_squares(n) {
+        unsafe { sieve.set_step_range_to_false(square, len, square) };
+    }
+    sieve
+}
+
+/**
+Parallel version of `squarefree`.
+Marks the multiples of every prime square in one adaptive pass, so rayon tasks own disjoint parts of the buffer.
+*/
+#[must_use]
+pub fn squarefree_par(n: usize) -> SieveVecBool {
+    squarefree_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `squarefree_par` whose marking mode, segment length and thread pool come from `config`
+#[must_use]
+pub fn squarefree_par_with(n: usize, config: &SieveConfig) -> SieveVecBool {
+    let mut sieve = initial_sieve(n);
+    let _ = sieve.set_multiples_of_slice_to_false_adaptive(&prime_squares(n), config);
+    sieve
+}
+
+/// The per-segment buffers `mobius_segment` works in, reused from one segment to the next.
+#[derive(Debug, Default, Clone)]
+struct Scratch {
+    /// Which values of the segment are squarefree.
+    squarefree: SieveVecBool,
+
+    /// The product of the distinct base primes dividing each value.
+    products: Vec<usize>,
+}
+
+/**
+Fills `segment` with μ(m) for `m` in `low..low + segment.len()`.
+Each base prime flips the sign of its multiples and multiplies itself into their product, and its square crosses them off
+the segment's squarefree sieve. A squarefree value whose product falls short of it has exactly one more prime factor,
+above the base primes, which flips its sign once more. `base_primes` must cover every prime up to the square root of the segment's last value.
+*/
+fn mobius_segment(segment: &mut [i8], low: usize, base_primes: &[usize], scratch: &mut Scratch) {
+    let len = segment.len();
+    let high = low + len;
+    let Scratch {
+        squarefree,
+        products,
+    } = scratch;
+    segment.fill(1);
+    squarefree.reset(len);
+    products.clear();
+    products.resize(len, 1);
+    for &p in base_primes {
+        for index in multiples_within(p, low, high) {
+            segment[index - low] = -segment[index - low];
+            products[index - low] *= p;
+        }
+        let square = p * p;
+        let start = first_in_progression(square, square, low);
+        if start < high {
+            unsafe { squarefree.set_step_range_to_false(start - low, len, square) };
+        }
+    }
+    for (((value, &is_squarefree), &product), m) in segment
+        .iter_mut()
+        .zip(squarefree.vec.iter())
+        .zip(products.iter())
+        .zip(low..)
+    {
+        *value = match (is_squarefree && m != 0, product == m) {
+            (false, _) => 0,
+            (true, true) => *value,
+            (true, false) => -*value,
+        };
+    }
+}
+
+/**
+Returns the table of the Möbius function μ(m) for every `m` in `0..=n`, with μ(0) = 0.
+μ(m) is 0 when a square above 1 divides `m`, and otherwise 1 or -1 as `m` has an even or odd number of prime factors.
+
+The table is filled one `DEFAULT_SEGMENT_LEN` segment at a time: the zeros come from a squarefree sieve of the segment
+crossed off with step `p * p`, and the signs from the multiples of each prime up to `sqrt(n)`.
+*/
+#[must_use]
+pub fn mobius(n: usize) -> Vec<i8> {
+    let base_primes = small_primes(n.isqrt());
+    let mut table = vec![0; n.saturating_add(1)];
+    fill_segments(&mut table, |segment, low, scratch| {
+        mobius_segment(segment, low, &base_primes, scratch);
+    });
+    table
+}
+
+/// Parallel version of `mobius`
+#[must_use]
+pub fn mobius_par(n: usize) -> Vec<i8> {
+    mobius_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `mobius_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn mobius_par_with(n: usize, config: &SieveConfig) -> Vec<i8> {
+    let base_primes = small_primes(n.isqrt());
+    let mut table = vec![0; n.saturating_add(1)];
+    fill_segments_par(&mut table, config, |segment, low, scratch| {
+        mobius_segment(segment, low, &base_primes, scratch);
+    });
+    table
+}
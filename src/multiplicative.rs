@@ -0,0 +1,145 @@
+use std::ops::Mul;
+
+use crate::primes::small_primes;
+use crate::segmented::{fill_segments, fill_segments_par};
+use crate::{SieveConfig, multiples_within};
+
+/**
+Fills `segment` with f(m) for `m` in `low..low + segment.len()`, using `remaining` as scratch.
+Each base prime `p` walks its multiples, dividing out its full power `p^k` from what remains of them and multiplying
+`f(p, k)` in. Any `q > 1` still left of a value is then a prime too large to be a base prime, and contributes `f(q, 1)`.
+`base_primes` must cover every prime up to the square root of the segment's last value.
+*/
+fn multiplicative_segment<T, F>(
+    segment: &mut [T],
+    low: usize,
+    base_primes: &[usize],
+    f: &F,
+    remaining: &mut Vec<u64>,
+) where
+    T: Copy + Mul<Output = T> + From<u8>,
+    F: Fn(u64, u32) -> T,
+{
+    let len = segment.len();
+    let high = low + len;
+    segment.fill(T::from(1));
+    remaining.clear();
+    remaining.extend((low..high).map(|m| m as u64));
+    for &p in base_primes {
+        let prime = p as u64;
+        for index in multiples_within(p, low, high) {
+            let rest = &mut remaining[index - low];
+            let mut k = 0;
+            while rest.is_multiple_of(prime) {
+                *rest /= prime;
+                k += 1;
+            }
+            segment[index - low] = segment[index - low] * f(prime, k);
+        }
+    }
+    for ((value, &rest), m) in segment.iter_mut().zip(remaining.iter()).zip(low..) {
+        if m == 0 {
+            *value = T::from(0);
+        } else if rest > 1 {
+            *value = *value * f(rest, 1);
+        }
+    }
+}
+
+/**
+Returns the table of the multiplicative function `f` for every `m` in `0..=n`, where the caller supplies `f(p, k)`,
+the value at the prime power `p^k`, and the table holds their product over the factorization of each `m`.
+By convention the table holds 1 at 1 and 0 at 0.
+
+The table is filled one `DEFAULT_SEGMENT_LEN` segment at a time, walking the multiples of each prime up to `sqrt(n)`,
+so besides the output only one segment's worth of scratch space is needed.
+*/
+#[must_use]
+pub fn multiplicative_sieve<T, F>(n: usize, f: F) -> Vec<T>
+where
+    T: Copy + Mul<Output = T> + From<u8>,
+    F: Fn(u64, u32) -> T,
+{
+    let base_primes = small_primes(n.isqrt());
+    let mut table = vec![T::from(0); n.saturating_add(1)];
+    fill_segments(&mut table, |segment, low, remaining| {
+        multiplicative_segment(segment, low, &base_primes, &f, remaining);
+    });
+    table
+}
+
+/// Parallel version of `multiplicative_sieve`
+#[must_use]
+pub fn multiplicative_sieve_par<T, F>(n: usize, f: F) -> Vec<T>
+where
+    T: Copy + Send + Sync + Mul<Output = T> + From<u8>,
+    F: Fn(u64, u32) -> T + Sync,
+{
+    multiplicative_sieve_par_with(n, f, &SieveConfig::default())
+}
+
+/// Version of `multiplicative_sieve_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn multiplicative_sieve_par_with<T, F>(n: usize, f: F, config: &SieveConfig) -> Vec<T>
+where
+    T: Copy + Send + Sync + Mul<Output = T> + From<u8>,
+    F: Fn(u64, u32) -> T + Sync,
+{
+    let base_primes = small_primes(n.isqrt());
+    let mut table = vec![T::from(0); n.saturating_add(1)];
+    fill_segments_par(&mut table, config, |segment, low, remaining| {
+        multiplicative_segment(segment, low, &base_primes, &f, remaining);
+    });
+    table
+}
+
+/// Returns d(p^k) = k + 1, the number of divisors of a prime power
+const fn divisor_count(_p: u64, k: u32) -> u32 {
+    k + 1
+}
+
+/// Returns σ(p^k) = 1 + p + ... + p^k, the sum of the divisors of a prime power
+fn divisor_sum(p: u64, k: u32) -> u64 {
+    (0..k)
+        .fold((1, 1), |(sum, power): (u64, u64), _| {
+            let power = power.saturating_mul(p);
+            (sum.saturating_add(power), power)
+        })
+        .0
+}
+
+/// Returns the table of d(m), the number of divisors of `m`, for every `m` in `0..=n`, via `multiplicative_sieve`
+#[must_use]
+pub fn divisor_counts(n: usize) -> Vec<u32> {
+    multiplicative_sieve(n, divisor_count)
+}
+
+/// Parallel version of `divisor_counts`
+#[must_use]
+pub fn divisor_counts_par(n: usize) -> Vec<u32> {
+    divisor_counts_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `divisor_counts_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn divisor_counts_par_with(n: usize, config: &SieveConfig) -> Vec<u32> {
+    multiplicative_sieve_par_with(n, divisor_count, config)
+}
+
+/// Returns the table of σ(m), the sum of the divisors of `m`, for every `m` in `0..=n`, via `multiplicative_sieve`
+#[must_use]
+pub fn divisor_sums(n: usize) -> Vec<u64> {
+    multiplicative_sieve(n, divisor_sum)
+}
+
+/// Parallel version of `divisor_sums`
+#[must_use]
+pub fn divisor_sums_par(n: usize) -> Vec<u64> {
+    divisor_sums_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `divisor_sums_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn divisor_sums_par_with(n: usize, config: &SieveConfig) -> Vec<u64> {
+    multiplicative_sieve_par_with(n, divisor_sum, config)
+}
@@ -0,0 +1,191 @@
+use crate::storage::coprime_sieve_prime_count;
+use crate::{SieveError, SieveStorage, SieveVecBool};
+
+/**
+A sieve over the odd numbers only, where index `i` holds the value `2i + 1`.
+Halves the memory of a plain sieve by never storing the even numbers, which `set_multiples_to_false(2)` would clear anyway.
+
+All methods take and report logical values, translating them to and from the compressed indices internally.
+The backing storage can be chosen with `S`, e.g. `OddSieve<SieveVecBit>` for one bit per odd number.
+*/
+#[derive(Debug, Default, Clone)]
+pub struct OddSieve<S = SieveVecBool> {
+    storage: S,
+    limit: usize,
+}
+
+impl<S: SieveStorage> OddSieve<S> {
+    /// Returns an `OddSieve` covering the odd numbers below `limit`, all set to true
+    #[must_use]
+    pub fn new(limit: usize) -> Self {
+        Self {
+            storage: S::with_len(limit / 2),
+            limit,
+        }
+    }
+
+    /// Returns the value stored at `index`
+    #[must_use]
+    pub const fn value_of(index: usize) -> usize {
+        2 * index + 1
+    }
+
+    /// Returns the index holding `value`, or `None` if `value` is even
+    #[must_use]
+    pub const fn index_of(value: usize) -> Option<usize> {
+        if value % 2 == 1 {
+            Some(value / 2)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the exclusive upper bound on the values this sieve covers
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the inner storage
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Returns whether `value` is still set, or `None` if `value` is even or not below `self.limit()`
+    #[must_use]
+    pub fn get(&self, value: usize) -> Option<bool> {
+        self.storage.get(Self::index_of(value)?)
+    }
+
+    /**
+    Sets `value` to false.
+    # Errors
+    Returns `SieveError::NotStored` if `value` is even or not below `self.limit()`.
+    */
+    pub fn set_false(&mut self, value: usize) -> Result<(), SieveError> {
+        match Self::index_of(value) {
+            Some(index) if index < self.storage.len() => {
+                unsafe { self.storage.set_false_unchecked(index) };
+                Ok(())
+            }
+            _ => Err(SieveError::NotStored {
+                value,
+                limit: self.limit,
+            }),
+        }
+    }
+
+    /**
+    Sets the odd multiples of `n` (`n`, `3n`, `5n`, ...) to false.
+    Even `n` have no odd multiples, so nothing is marked for them.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false(&mut self, n: usize) -> Result<(), SieveError> {
+        let Some(start) = Self::index_of(n) else {
+            return if n == 0 {
+                Err(SieveError::ZeroStep)
+            } else {
+                Ok(())
+            };
+        };
+        // Consecutive odd multiples of `n` are `2n` apart in value and therefore `n` apart in index.
+        let len = self.storage.len();
+        unsafe { self.storage.set_step_range_to_false(start, len, n) };
+        Ok(())
+    }
+
+    /**
+    Parallel version of `set_multiples_to_false`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false_par(&mut self, n: usize) -> Result<(), SieveError> {
+        let Some(start) = Self::index_of(n) else {
+            return if n == 0 {
+                Err(SieveError::ZeroStep)
+            } else {
+                Ok(())
+            };
+        };
+        let len = self.storage.len();
+        unsafe { self.storage.set_step_range_to_false_par(start, len, n) };
+        Ok(())
+    }
+
+    /**
+    Crosses off the odd prime `p`: its odd multiples from `p * p` onwards are set to false, leaving `p` itself set.
+    Does nothing for even `p`, whose multiples are never stored, or if `p * p` overflows.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        if let Some(start) = Self::square_index(p).filter(|&start| start < len) {
+            unsafe { self.storage.set_step_range_to_false(start, len, p) };
+        }
+        Ok(())
+    }
+
+    /**
+    Parallel version of `cross_off_prime`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime_par(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        if let Some(start) = Self::square_index(p).filter(|&start| start < len) {
+            unsafe { self.storage.set_step_range_to_false_par(start, len, p) };
+        }
+        Ok(())
+    }
+
+    /// Returns the index of `p * p`, or `None` if `p` is even or the square overflows
+    fn square_index(p: usize) -> Option<usize> {
+        Self::index_of(p.checked_mul(p)?)
+    }
+
+    /**
+    Returns the number of primes below `self.limit()` in a finished sieve: the values other than 1 that are still set, plus 2.
+    Every odd prime below `sqrt(self.limit())` must have been crossed off for the count to be exact.
+    */
+    #[must_use]
+    pub fn count_primes(&self) -> usize {
+        coprime_sieve_prime_count(
+            &self.storage,
+            self.storage.count_ones(),
+            self.two_is_covered(),
+        )
+    }
+
+    /// Parallel version of `count_primes`
+    #[must_use]
+    pub fn count_primes_par(&self) -> usize {
+        coprime_sieve_prime_count(
+            &self.storage,
+            self.storage.count_ones_par(),
+            self.two_is_covered(),
+        )
+    }
+
+    /// Returns 1 if 2, the one even prime and so never stored, is below `self.limit()`
+    fn two_is_covered(&self) -> usize {
+        usize::from(self.limit > 2)
+    }
+
+    /// Returns an iterator over the values that are still set, in ascending order
+    pub fn values(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.storage.len())
+            .filter(|&index| self.storage.get(index) == Some(true))
+            .map(Self::value_of)
+    }
+}
@@ -0,0 +1,91 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::linear::linear_table;
+use crate::{Algorithm, SieveConfig, SieveStorage, SieveVecBool};
+
+/// Returns a sieve of length `n + 1` with 0 and 1 already cleared
+fn initial_sieve(n: usize) -> SieveVecBool {
+    let mut sieve = SieveVecBool::with_len(n.saturating_add(1));
+    for index in 0..sieve.len().min(2) {
+        unsafe { sieve.set_false_unchecked(index) };
+    }
+    sieve
+}
+
+/// Returns the indices of a finished sieve that are still set
+fn collect_primes(sieve: SieveVecBool) -> Vec<u64> {
+    sieve
+        .into_inner()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(n, is_prime)| is_prime.then_some(n as u64))
+        .collect()
+}
+
+/// Parallel version of `collect_primes`
+fn collect_primes_par(sieve: SieveVecBool) -> Vec<u64> {
+    sieve
+        .into_inner()
+        .into_par_iter()
+        .enumerate()
+        .filter_map(|(n, is_prime)| is_prime.then_some(n as u64))
+        .collect()
+}
+
+/// Runs the sequential sieve of Eratosthenes, returning a sieve where index `i` is set iff `i` is prime
+pub fn sieve_up_to(n: usize) -> SieveVecBool {
+    let mut sieve = initial_sieve(n);
+    for p in 2..=n.isqrt() {
+        if sieve.get(p) == Some(true) {
+            let _ = sieve.cross_off_prime(p);
+        }
+    }
+    sieve
+}
+
+/// Returns every prime less than or equal to `n` as indices, for use as base primes
+pub fn small_primes(n: usize) -> Vec<usize> {
+    sieve_up_to(n)
+        .into_inner()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(n, is_prime)| is_prime.then_some(n))
+        .collect()
+}
+
+/**
+Returns every prime less than or equal to `n`, in ascending order.
+Runs the sieve of Eratosthenes on a `SieveVecBool`, crossing off the multiples of each prime `p` from `p * p`.
+*/
+#[must_use]
+pub fn primes_up_to(n: usize) -> Vec<u64> {
+    collect_primes(sieve_up_to(n))
+}
+
+/// Version of `primes_up_to` running the chosen `algorithm`, so the two can be benchmarked against each other
+#[must_use]
+pub fn primes_up_to_with(n: usize, algorithm: Algorithm) -> Vec<u64> {
+    match algorithm {
+        Algorithm::Eratosthenes => primes_up_to(n),
+        Algorithm::Linear => linear_table(n).0,
+    }
+}
+
+/**
+Parallel version of `primes_up_to`.
+The primes up to `sqrt(n)` are found sequentially, then the rest of the sieve is split into segments
+that rayon tasks cross off independently, so no two threads ever write to the same part of the buffer.
+*/
+#[must_use]
+pub fn primes_up_to_par(n: usize) -> Vec<u64> {
+    primes_up_to_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `primes_up_to_par` whose marking mode, segment length and thread pool come from `config`
+#[must_use]
+pub fn primes_up_to_par_with(n: usize, config: &SieveConfig) -> Vec<u64> {
+    let base_primes = small_primes(n.isqrt());
+    let mut sieve = initial_sieve(n);
+    let _ = sieve.cross_off_primes_adaptive(&base_primes, config);
+    config.install(|| collect_primes_par(sieve))
+}
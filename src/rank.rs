@@ -0,0 +1,113 @@
+use crate::{SieveVecBit, SieveVecBool, WORD_BITS, WORDS_PER_BLOCK};
+
+/**
+A read-only index over a finished sieve answering rank and select queries.
+`rank` is O(1), using a cumulative count every 512 bits plus at most eight popcounts,
+and `select` is O(log n), binary searching those counts before scanning one block.
+The counts take an eighth of the memory of the bits themselves.
+
+Built from a `SieveVecBit` or `SieveVecBool` once marking has finished, e.g. a prime sieve with 0 and 1 cleared,
+where `rank(k + 1)` is the number of primes up to `k` and `select(j)` is the `j`th prime counting from 0.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RankSelect {
+    words: Vec<u64>,
+    len: usize,
+
+    /// `blocks[b]` is the number of set bits before word `b * WORDS_PER_BLOCK`, with the total count appended.
+    blocks: Vec<usize>,
+}
+
+impl From<SieveVecBit> for RankSelect {
+    fn from(sieve: SieveVecBit) -> Self {
+        let len = sieve.len();
+        let words = sieve.into_inner();
+        let mut blocks = Vec::with_capacity(words.len().div_ceil(WORDS_PER_BLOCK) + 1);
+        let mut count = 0;
+        for block in words.chunks(WORDS_PER_BLOCK) {
+            blocks.push(count);
+            count += block
+                .iter()
+                .map(|word| word.count_ones() as usize)
+                .sum::<usize>();
+        }
+        blocks.push(count);
+        Self { words, len, blocks }
+    }
+}
+
+impl From<SieveVecBool> for RankSelect {
+    fn from(sieve: SieveVecBool) -> Self {
+        SieveVecBit::from(sieve.into_inner()).into()
+    }
+}
+
+impl RankSelect {
+    /// Returns the number of elements
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no elements
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements that are set
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.blocks.last().copied().unwrap_or(0)
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of range
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0)
+    }
+
+    /// Returns the number of set elements in `0..index`, which is `self.count_ones()` for any `index` past the end
+    #[must_use]
+    pub fn rank(&self, index: usize) -> usize {
+        if index >= self.len {
+            return self.count_ones();
+        }
+        let word = index / WORD_BITS;
+        let block_start = word - word % WORDS_PER_BLOCK;
+        let whole_words: usize = self.words[block_start..word]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        let partial = self.words[word] & ((1 << (index % WORD_BITS)) - 1);
+        self.blocks[word / WORDS_PER_BLOCK] + whole_words + partial.count_ones() as usize
+    }
+
+    /// Returns the index of the set element with `rank` set elements before it, or `None` if there are not that many
+    #[must_use]
+    pub fn select(&self, rank: usize) -> Option<usize> {
+        if rank >= self.count_ones() {
+            return None;
+        }
+        // The last block starting with at most `rank` set bits before it holds the answer.
+        let block = self.blocks.partition_point(|&count| count <= rank) - 1;
+        let mut remaining = rank - self.blocks[block];
+        for (offset, &word) in self.words[block * WORDS_PER_BLOCK..].iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                let word_index = block * WORDS_PER_BLOCK + offset;
+                return Some(word_index * WORD_BITS + select_in_word(word, remaining));
+            }
+            remaining -= ones;
+        }
+        None
+    }
+}
+
+/// Returns the position of the set bit in `word` with `rank` set bits below it, which must exist
+fn select_in_word(mut word: u64, rank: usize) -> usize {
+    for _ in 0..rank {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
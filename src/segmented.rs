@@ -0,0 +1,206 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+
+use crate::primes::small_primes;
+use crate::{SieveConfig, SieveVecBool, to_index};
+
+/// Default number of candidates per segment, sized so a `SieveVecBool` segment sits comfortably in L2 cache.
+pub const DEFAULT_SEGMENT_LEN: usize = 1 << 18;
+
+/// Returns every prime less than or equal to `sqrt(high - 1)`, enough to sieve anything below `high`
+pub fn base_primes_below(high: u64) -> Vec<u64> {
+    small_primes(to_index(high.saturating_sub(1).isqrt()))
+        .into_iter()
+        .map(|p| p as u64)
+        .collect()
+}
+
+/**
+Sieves `segment`, which represents the values `low..low + segment.len()` and must be entirely set on entry.
+For each base prime `p` the first multiple crossed off is the larger of `p * p` and the first multiple at or after `low`,
+so primes inside the segment are kept. `base_primes` must cover every prime up to the square root of the segment's last value.
+*/
+pub fn sieve_segment(segment: &mut SieveVecBool, low: u64, base_primes: &[u64]) {
+    let len = segment.len();
+    let high = low.saturating_add(len as u64);
+    for index in 0..to_index(2u64.saturating_sub(low)).min(len) {
+        unsafe { segment.set_false_unchecked(index) };
+    }
+    for &p in base_primes {
+        let Some(square) = p.checked_mul(p).filter(|&square| square < high) else {
+            break;
+        };
+        let Some(first) = low.div_ceil(p).checked_mul(p) else {
+            continue;
+        };
+        let start = to_index(square.max(first) - low);
+        unsafe { segment.set_step_range_to_false(start.min(len), len, to_index(p)) };
+    }
+}
+
+/**
+Fills `table` one `DEFAULT_SEGMENT_LEN` segment at a time, calling `fill(segment, low, scratch)` with the index `low`
+of each segment's first entry. All segments share one scratch value, so its buffers are allocated only once.
+*/
+pub fn fill_segments<T, S: Default>(
+    table: &mut [T],
+    mut fill: impl FnMut(&mut [T], usize, &mut S),
+) {
+    let mut scratch = S::default();
+    for (segment_index, segment) in table.chunks_mut(DEFAULT_SEGMENT_LEN).enumerate() {
+        fill(segment, segment_index * DEFAULT_SEGMENT_LEN, &mut scratch);
+    }
+}
+
+/**
+Parallel version of `fill_segments`, with segments of `config.segment_len` filled in `config`'s thread pool.
+Each rayon task owns a disjoint segment of the table and its own scratch value, so no two threads ever share a write.
+*/
+pub fn fill_segments_par<T: Send, S: Default>(
+    table: &mut [T],
+    config: &SieveConfig,
+    fill: impl Fn(&mut [T], usize, &mut S) + Sync + Send,
+) {
+    let segment_len = config.segment_len.max(1);
+    config.install(|| {
+        table.par_chunks_mut(segment_len).enumerate().for_each_init(
+            S::default,
+            |scratch, (segment_index, segment)| {
+                fill(segment, segment_index * segment_len, scratch);
+            },
+        );
+    });
+}
+
+/// Returns the values of a sieved segment starting at `low` that are still set
+pub fn collect_segment(segment: &SieveVecBool, low: u64) -> Vec<u64> {
+    segment
+        .vec
+        .iter()
+        .zip(low..)
+        .filter_map(|(&is_prime, n)| is_prime.then_some(n))
+        .collect()
+}
+
+/**
+Returns every prime in `lo..hi`, in ascending order.
+Allocates a `SieveVecBool` covering only `hi - lo` values, with index `i` standing for `lo + i`,
+and crosses it off with the base primes up to `sqrt(hi)`, so windows far from zero cost no more than windows near it.
+*/
+#[must_use]
+pub fn primes_in_range(lo: u64, hi: u64) -> Vec<u64> {
+    if hi <= lo {
+        return Vec::new();
+    }
+    let mut window = SieveVecBool::with_len(to_index(hi - lo));
+    sieve_segment(&mut window, lo, &base_primes_below(hi));
+    collect_segment(&window, lo)
+}
+
+/**
+A segmented sieve of Eratosthenes over the values `0..=n`.
+Only the base primes up to `sqrt(n)` and one segment buffer are held in memory, so memory use is
+`O(sqrt(n) + segment_len)` rather than `O(n)`, which makes limits far beyond what fits in a single `SieveVecBool` practical.
+
+Iterating yields the primes of one segment at a time, in ascending order; `.flatten()` gives the primes themselves.
+*/
+#[derive(Debug, Clone)]
+pub struct SegmentedSieve {
+    base_primes: Vec<u64>,
+    segment: SieveVecBool,
+    segment_len: usize,
+    low: u64,
+    high: u64,
+}
+
+impl SegmentedSieve {
+    /// Returns a `SegmentedSieve` over `0..=n` using segments of `DEFAULT_SEGMENT_LEN`
+    #[must_use]
+    pub fn new(n: u64) -> Self {
+        Self::with_segment_len(n, DEFAULT_SEGMENT_LEN)
+    }
+
+    /**
+    Returns a `SegmentedSieve` over `0..=n` using segments of `segment_len` values.
+    # Panics
+    Panics if `segment_len` is zero.
+    */
+    #[must_use]
+    pub fn with_segment_len(n: u64, segment_len: usize) -> Self {
+        assert!(segment_len > 0, "segment length must be non-zero");
+        let high = n.saturating_add(1);
+        Self {
+            base_primes: base_primes_below(high),
+            segment: SieveVecBool::new(),
+            segment_len,
+            low: 0,
+            high,
+        }
+    }
+
+    /// Returns the length of each segment
+    #[must_use]
+    pub const fn segment_len(&self) -> usize {
+        self.segment_len
+    }
+
+    /// Returns the base primes used to sieve each segment
+    #[must_use]
+    pub fn base_primes(&self) -> &[u64] {
+        &self.base_primes
+    }
+
+    /**
+    Sieves every remaining segment in parallel and returns their primes in ascending order.
+    Each rayon worker owns its own segment buffer and sieves whole segments independently, so threads never share
+    a mutable buffer; the per-segment results are then concatenated in order.
+    */
+    #[must_use]
+    pub fn primes_par(self) -> Vec<u64> {
+        self.primes_par_with(&SieveConfig::default())
+    }
+
+    /// Version of `primes_par` that runs on `config`'s thread pool
+    #[must_use]
+    pub fn primes_par_with(self, config: &SieveConfig) -> Vec<u64> {
+        config.install(|| self.sieve_remaining_par())
+    }
+
+    /// Does the work of `primes_par` on whichever pool it is called from
+    fn sieve_remaining_par(self) -> Vec<u64> {
+        let Self {
+            base_primes,
+            segment_len,
+            low,
+            high,
+            ..
+        } = self;
+        let segments = to_index(high.saturating_sub(low).div_ceil(segment_len as u64));
+        let per_segment: Vec<Vec<u64>> = (0..segments)
+            .into_par_iter()
+            .map_init(SieveVecBool::new, |segment, segment_index| {
+                let segment_low = low + segment_index as u64 * segment_len as u64;
+                segment.reset(to_index(high - segment_low).min(segment_len));
+                sieve_segment(segment, segment_low, &base_primes);
+                collect_segment(segment, segment_low)
+            })
+            .collect();
+        per_segment.concat()
+    }
+}
+
+impl Iterator for SegmentedSieve {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.low >= self.high {
+            return None;
+        }
+        let low = self.low;
+        let len = to_index(self.high - low).min(self.segment_len);
+        self.segment.reset(len);
+        sieve_segment(&mut self.segment, low, &self.base_primes);
+        self.low = low.saturating_add(len as u64);
+        Some(collect_segment(&self.segment, low))
+    }
+}
@@ -0,0 +1,158 @@
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+
+use crate::linear::linear_table;
+use crate::primes::small_primes;
+use crate::{Algorithm, SieveConfig, progression_within};
+
+/**
+Fills `chunk`, which holds the entries for `offset..offset + chunk.len()`, with smallest prime factors.
+`base_primes` must be ascending and cover every prime up to the square root of the chunk's last value;
+because they are ascending, the first prime to reach an entry is its smallest factor and later ones leave it alone.
+*/
+fn sieve_chunk(chunk: &mut [u32], offset: usize, base_primes: &[usize]) {
+    chunk.fill(0);
+    let stop = offset + chunk.len();
+    for &p in base_primes {
+        let Some(square) = p.checked_mul(p).filter(|&square| square < stop) else {
+            break;
+        };
+        let factor = u32::try_from(p).unwrap_or(u32::MAX);
+        for index in progression_within(square, p, offset, stop) {
+            let entry = &mut chunk[index - offset];
+            if *entry == 0 {
+                *entry = factor;
+            }
+        }
+    }
+    // Whatever no base prime reached is prime (or 0 or 1), and is its own smallest factor.
+    for (entry, n) in chunk.iter_mut().zip(offset..) {
+        if *entry == 0 {
+            *entry = u32::try_from(n).unwrap_or(u32::MAX);
+        }
+    }
+}
+
+/**
+A table of the smallest prime factor of every `n` up to a limit, stored as `u32`s.
+Any `n` in range can then be factorized by repeatedly dividing out its smallest prime factor,
+taking one table lookup per prime factor and so `O(log n)` steps.
+
+Takes four times the memory of a `SieveVecBool` over the same range.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpfSieve {
+    spf: Vec<u32>,
+}
+
+impl SpfSieve {
+    /// Returns the smallest-prime-factor table for every value up to and including `n`
+    #[must_use]
+    pub fn new(n: u32) -> Self {
+        let mut spf = vec![0; n as usize + 1];
+        sieve_chunk(&mut spf, 0, &small_primes(n.isqrt() as usize));
+        Self { spf }
+    }
+
+    /// Version of `new` running the chosen `algorithm`, so the two can be benchmarked against each other
+    #[must_use]
+    pub fn with_algorithm(n: u32, algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Eratosthenes => Self::new(n),
+            Algorithm::Linear => linear_sieve(n).1,
+        }
+    }
+
+    /**
+    Parallel version of `new`.
+    The table is split into segments that rayon tasks fill independently, each task crossing off
+    every base prime inside its own segment, so no two threads ever write to the same entry.
+    */
+    #[must_use]
+    pub fn new_par(n: u32) -> Self {
+        Self::new_par_with(n, &SieveConfig::default())
+    }
+
+    /// Version of `new_par` whose segment length and thread pool come from `config`
+    #[must_use]
+    pub fn new_par_with(n: u32, config: &SieveConfig) -> Self {
+        let base_primes = small_primes(n.isqrt() as usize);
+        let segment_len = config.segment_len.max(1);
+        let mut spf = vec![0; n as usize + 1];
+        config.install(|| {
+            spf.par_chunks_mut(segment_len)
+                .enumerate()
+                .for_each(|(segment_index, segment)| {
+                    sieve_chunk(segment, segment_index * segment_len, &base_primes);
+                });
+        });
+        Self { spf }
+    }
+
+    /// Returns the largest value in the table
+    #[must_use]
+    pub fn limit(&self) -> u32 {
+        u32::try_from(self.spf.len().saturating_sub(1)).unwrap_or(u32::MAX)
+    }
+
+    /// Returns the inner table, where entry `n` is the smallest prime factor of `n` for `n >= 2` and `n` itself otherwise
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u32> {
+        self.spf
+    }
+
+    /// Returns the smallest prime factor of `n`, or `None` if `n` is below 2 or above `self.limit()`
+    #[must_use]
+    pub fn get(&self, n: u32) -> Option<u32> {
+        if n < 2 {
+            return None;
+        }
+        self.spf.get(n as usize).copied()
+    }
+
+    /// Returns whether `n` is prime, or `None` if `n` is above `self.limit()`
+    #[must_use]
+    pub fn is_prime(&self, n: u32) -> Option<bool> {
+        let spf = self.spf.get(n as usize)?;
+        Some(n >= 2 && *spf == n)
+    }
+
+    /**
+    Returns the prime factors of `n` in ascending order, repeated according to their multiplicity,
+    or `None` if `n` is 0 or above `self.limit()`. 1 has no prime factors.
+    */
+    #[must_use]
+    pub fn factorize(&self, n: u32) -> Option<Vec<u32>> {
+        if n == 0 || n as usize >= self.spf.len() {
+            return None;
+        }
+        let mut factors = Vec::new();
+        let mut rest = n;
+        while rest > 1 {
+            let p = self.spf[rest as usize];
+            factors.push(p);
+            rest /= p;
+        }
+        Some(factors)
+    }
+}
+
+/**
+Runs Euler's linear sieve over `0..=n`, returning every prime up to `n` together with the smallest-prime-factor table.
+Visits each composite exactly once, so runs in `O(n)` rather than the `O(n log log n)` of `SpfSieve::new`,
+though the sequential memory access of Eratosthenes is often faster in practice.
+*/
+#[must_use]
+pub fn linear_sieve(n: u32) -> (Vec<u32>, SpfSieve) {
+    let (primes, mut spf) = linear_table(n as usize);
+    for (entry, value) in spf.iter_mut().zip(0..) {
+        if *entry == 0 {
+            *entry = value;
+        }
+    }
+    let primes = primes
+        .into_iter()
+        .map(|p| u32::try_from(p).unwrap_or(u32::MAX))
+        .collect();
+    (primes, SpfSieve { spf })
+}
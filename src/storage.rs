@@ -0,0 +1,291 @@
+use crate::error::validate_step_range;
+use crate::{SieveError, SieveVecBit, SieveVecBool};
+
+/**
+The marking API shared by the sieve backends.
+Algorithms written against this trait can be run on byte-backed (`SieveVecBool`) or bit-backed (`SieveVecBit`) storage,
+so the two can be compared for speed vs. memory by changing a single type parameter.
+*/
+pub trait SieveStorage: Sized {
+    /// Returns storage of length `len` with every element set to true
+    fn with_len(len: usize) -> Self;
+
+    /// Returns the number of elements
+    fn len(&self) -> usize;
+
+    /// Returns true if there are no elements
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of range
+    fn get(&self, index: usize) -> Option<bool>;
+
+    /// Returns the number of elements still set to true
+    fn count_ones(&self) -> usize;
+
+    /// Parallel version of `count_ones`
+    fn count_ones_par(&self) -> usize;
+
+    /**
+    Sets the element at `index` to false.
+    # Safety
+    `index` must be less than `self.len()`
+    */
+    unsafe fn set_false_unchecked(&mut self, index: usize);
+
+    /**
+    Sets all the indices in `(start..stop).step_by(step_size)` to false.
+    # Safety
+    all elements in `(start..stop).step_by(step_size)` must be valid indices.
+    */
+    unsafe fn set_step_range_to_false(&mut self, start: usize, stop: usize, step_size: usize);
+
+    /**
+    Parallel version of `set_step_range_to_false`.
+    # Safety
+    all elements in `(start..stop).step_by(step_size)` must be valid indices.
+    */
+    unsafe fn set_step_range_to_false_par(&mut self, start: usize, stop: usize, step_size: usize);
+
+    /**
+    Sets every multiple of every element of `slice` (including the element itself) to false, in parallel.
+    # Safety
+    Every element of `slice` must be non-zero.
+    */
+    unsafe fn set_multiples_of_slice_to_false_par(&mut self, slice: &[usize]);
+
+    /**
+    Sets the element at `index` to false.
+    # Errors
+    Returns `SieveError::OutOfRange` if `index` is not less than `self.len()`.
+    */
+    fn set_false(&mut self, index: usize) -> Result<(), SieveError> {
+        let len = self.len();
+        if index >= len {
+            return Err(SieveError::OutOfRange { index, len });
+        }
+        unsafe { self.set_false_unchecked(index) };
+        Ok(())
+    }
+
+    /**
+    Safe counterpart of `set_step_range_to_false`.
+    The whole range is validated before anything is written, so on error the storage is left untouched.
+
+    # Errors
+    - `SieveError::ZeroStep` if `step_size` is zero.
+    - `SieveError::StartAfterStop` if `start > stop`.
+    - `SieveError::OutOfRange` if any index in the range is not a valid index.
+    */
+    fn set_step_range_to_false_checked(
+        &mut self,
+        start: usize,
+        stop: usize,
+        step_size: usize,
+    ) -> Result<(), SieveError> {
+        validate_step_range(start, stop, step_size, self.len())?;
+        unsafe { self.set_step_range_to_false(start, stop, step_size) };
+        Ok(())
+    }
+
+    /**
+    Safe counterpart of `set_step_range_to_false_par`.
+    # Errors
+    Same as `set_step_range_to_false_checked`.
+    */
+    fn set_step_range_to_false_par_checked(
+        &mut self,
+        start: usize,
+        stop: usize,
+        step_size: usize,
+    ) -> Result<(), SieveError> {
+        validate_step_range(start, stop, step_size, self.len())?;
+        unsafe { self.set_step_range_to_false_par(start, stop, step_size) };
+        Ok(())
+    }
+
+    /**
+    Sets every multiple of `n` to false, `n` itself included, like the backends' unchecked `set_multiples_to_false`.
+    Multiples of `n` that lie beyond the end are ignored.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    fn set_multiples_to_false_checked(&mut self, n: usize) -> Result<(), SieveError> {
+        if n == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        unsafe { self.set_step_range_to_false(n, len, n) };
+        Ok(())
+    }
+
+    /**
+    Parallel version of `set_multiples_to_false_checked`.
+    # Errors
+    Same as `set_multiples_to_false_checked`.
+    */
+    fn set_multiples_to_false_par_checked(&mut self, n: usize) -> Result<(), SieveError> {
+        if n == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        unsafe { self.set_step_range_to_false_par(n, len, n) };
+        Ok(())
+    }
+
+    /**
+    Safe counterpart of `set_multiples_of_slice_to_false_par`.
+    Every element of `slice` is validated before anything is written.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `slice` contains a zero.
+    */
+    fn set_multiples_of_slice_to_false_par_checked(
+        &mut self,
+        slice: &[usize],
+    ) -> Result<(), SieveError> {
+        if slice.contains(&0) {
+            return Err(SieveError::ZeroStep);
+        }
+        unsafe { self.set_multiples_of_slice_to_false_par(slice) };
+        Ok(())
+    }
+
+    /**
+    Sets every multiple of `n` to false, `n` itself included.
+    Prefer `cross_off_prime` when sieving for primes, as this clears the prime too.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    fn set_multiples_including_self(&mut self, n: usize) -> Result<(), SieveError> {
+        self.set_multiples_to_false_checked(n)
+    }
+
+    /**
+    Crosses off the prime `p` the way a sieve of Eratosthenes needs: every multiple of `p` from `p * p` onwards is set to false,
+    leaving `p` itself and the smaller multiples (already crossed off by smaller primes) alone.
+    Does nothing if `p * p` is past the end or overflows.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    fn cross_off_prime(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        if let Some(start) = p.checked_mul(p).filter(|&start| start < len) {
+            unsafe { self.set_step_range_to_false(start, len, p) };
+        }
+        Ok(())
+    }
+
+    /**
+    Parallel version of `cross_off_prime`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    fn cross_off_prime_par(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.len();
+        if let Some(start) = p.checked_mul(p).filter(|&start| start < len) {
+            unsafe { self.set_step_range_to_false_par(start, len, p) };
+        }
+        Ok(())
+    }
+}
+
+/**
+Returns the number of primes in a finished sieve over the values coprime to a few small primes, such as `OddSieve`
+and `WheelSieve`, whose index 0 holds the value 1. `set` is the number of elements of `storage` still set, and
+`unstored_primes` the number of those small primes, which are never stored, that lie below the sieve's limit.
+*/
+pub fn coprime_sieve_prime_count<S: SieveStorage>(
+    storage: &S,
+    set: usize,
+    unstored_primes: usize,
+) -> usize {
+    // 1 is stored but not prime, so it is left out of the count unless it has already been cleared.
+    set + unstored_primes - usize::from(storage.get(0) == Some(true))
+}
+
+impl SieveStorage for SieveVecBool {
+    fn with_len(len: usize) -> Self {
+        Self::with_len(len)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, index: usize) -> Option<bool> {
+        self.get(index)
+    }
+
+    fn count_ones(&self) -> usize {
+        self.count_ones()
+    }
+
+    fn count_ones_par(&self) -> usize {
+        self.count_ones_par()
+    }
+
+    unsafe fn set_false_unchecked(&mut self, index: usize) {
+        unsafe { self.set_false_unchecked(index) };
+    }
+
+    unsafe fn set_step_range_to_false(&mut self, start: usize, stop: usize, step_size: usize) {
+        unsafe { self.set_step_range_to_false(start, stop, step_size) };
+    }
+
+    unsafe fn set_step_range_to_false_par(&mut self, start: usize, stop: usize, step_size: usize) {
+        unsafe { self.set_step_range_to_false_par(start, stop, step_size) };
+    }
+
+    unsafe fn set_multiples_of_slice_to_false_par(&mut self, slice: &[usize]) {
+        unsafe { self.set_multiples_of_slice_to_false_par(slice) };
+    }
+}
+
+impl SieveStorage for SieveVecBit {
+    fn with_len(len: usize) -> Self {
+        Self::with_len(len)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, index: usize) -> Option<bool> {
+        self.get(index)
+    }
+
+    fn count_ones(&self) -> usize {
+        self.count_ones()
+    }
+
+    fn count_ones_par(&self) -> usize {
+        self.count_ones_par()
+    }
+
+    unsafe fn set_false_unchecked(&mut self, index: usize) {
+        unsafe { self.set_false_unchecked(index) };
+    }
+
+    unsafe fn set_step_range_to_false(&mut self, start: usize, stop: usize, step_size: usize) {
+        unsafe { self.set_step_range_to_false(start, stop, step_size) };
+    }
+
+    unsafe fn set_step_range_to_false_par(&mut self, start: usize, stop: usize, step_size: usize) {
+        unsafe { self.set_step_range_to_false_par(start, stop, step_size) };
+    }
+
+    unsafe fn set_multiples_of_slice_to_false_par(&mut self, slice: &[usize]) {
+        unsafe { self.set_multiples_of_slice_to_false_par(slice) };
+    }
+}
@@ -0,0 +1,202 @@
+use crate::segmented::{base_primes_below, fill_segments, fill_segments_par};
+use crate::{DEFAULT_SEGMENT_LEN, SieveConfig, multiples_within, to_index};
+
+/**
+The integer types a totient table can be produced in.
+Since φ(m) never exceeds `m`, a table up to `n` fits any type `n` itself fits in.
+*/
+pub trait TotientValue: Copy + Default + Send + Sync + Into<u64> {
+    /// Converts `value`, which is known to fit, from `u64`
+    fn from_u64(value: u64) -> Self;
+}
+
+impl TotientValue for u32 {
+    fn from_u64(value: u64) -> Self {
+        Self::try_from(value).unwrap_or(Self::MAX)
+    }
+}
+
+impl TotientValue for u64 {
+    fn from_u64(value: u64) -> Self {
+        value
+    }
+}
+
+/// The per-segment buffers `totient_segment` works in, reused from one segment to the next.
+#[derive(Debug, Default, Clone)]
+struct Scratch {
+    /// The totient of each value so far, over the primes handled yet.
+    phi: Vec<u64>,
+
+    /// What is left of each value once the powers of the primes handled yet are divided out.
+    remaining: Vec<u64>,
+}
+
+/**
+Fills `segment` with φ(m) for `m` in `low..low + segment.len()`.
+Each base prime `p` walks its multiples, scaling their totient by `(p - 1) / p`, then each prime power `p^k`
+walks its own multiples to divide `p` out of what remains of them. Whatever remains above 1 afterwards is the single
+prime factor above the base primes. `base_primes` must cover every prime up to the square root of the segment's last value.
+*/
+fn totient_segment<T: TotientValue>(
+    segment: &mut [T],
+    low: u64,
+    base_primes: &[u64],
+    scratch: &mut Scratch,
+) {
+    let len = segment.len();
+    let high = low.saturating_add(len as u64);
+    let Scratch { phi, remaining } = scratch;
+    phi.clear();
+    phi.extend(low..high);
+    remaining.clear();
+    remaining.extend(low..high);
+
+    let offset = to_index(low);
+    let end = offset + len;
+    for &p in base_primes {
+        let step = to_index(p);
+        for index in multiples_within(step, offset, end) {
+            let value = &mut phi[index - offset];
+            *value = *value / p * (p - 1);
+        }
+        let mut power = p;
+        while power < high {
+            let step = to_index(power);
+            for index in multiples_within(step, offset, end) {
+                remaining[index - offset] /= p;
+            }
+            let Some(next) = power.checked_mul(p) else {
+                break;
+            };
+            power = next;
+        }
+    }
+    for ((value, &phi), &rest) in segment.iter_mut().zip(phi.iter()).zip(remaining.iter()) {
+        *value = T::from_u64(if rest > 1 {
+            phi / rest * (rest - 1)
+        } else {
+            phi
+        });
+    }
+}
+
+/**
+Returns the table of Euler's totient φ(m) for every `m` in `0..=n`, with φ(0) = 0.
+The output type follows `n`, so `totients(n as u32)` gives a `Vec<u32>` at half the memory of `totients(n as u64)`.
+
+The table is filled one `DEFAULT_SEGMENT_LEN` segment at a time, crossing off the multiples of each prime up to `sqrt(n)`
+and of its powers, so the scratch space stays small however large `n` is.
+*/
+#[must_use]
+pub fn totients<T: TotientValue>(n: T) -> Vec<T> {
+    let high = n.into().saturating_add(1);
+    let base_primes = base_primes_below(high);
+    let mut table = vec![T::default(); to_index(high)];
+    fill_segments(&mut table, |segment, low, scratch| {
+        totient_segment(segment, low as u64, &base_primes, scratch);
+    });
+    table
+}
+
+/// Parallel version of `totients`
+#[must_use]
+pub fn totients_par<T: TotientValue>(n: T) -> Vec<T> {
+    totients_par_with(n, &SieveConfig::default())
+}
+
+/// Version of `totients_par` whose segment length and thread pool come from `config`
+#[must_use]
+pub fn totients_par_with<T: TotientValue>(n: T, config: &SieveConfig) -> Vec<T> {
+    let high = n.into().saturating_add(1);
+    let base_primes = base_primes_below(high);
+    let mut table = vec![T::default(); to_index(high)];
+    fill_segments_par(&mut table, config, |segment, low, scratch| {
+        totient_segment(segment, low as u64, &base_primes, scratch);
+    });
+    table
+}
+
+/**
+Returns φ(m) for every `m` in `lo..hi`, with index `i` standing for `lo + i`.
+Only needs the base primes up to `sqrt(hi)` besides the output, so windows far from zero cost no more than windows near it.
+*/
+#[must_use]
+pub fn totients_in_range<T: TotientValue>(lo: T, hi: T) -> Vec<T> {
+    let (lo, hi) = (lo.into(), hi.into());
+    if hi <= lo {
+        return Vec::new();
+    }
+    let mut window = vec![T::default(); to_index(hi - lo)];
+    totient_segment(
+        &mut window,
+        lo,
+        &base_primes_below(hi),
+        &mut Scratch::default(),
+    );
+    window
+}
+
+/**
+A segmented totient sieve over the values `0..=n`.
+Only the base primes up to `sqrt(n)` and one segment's buffers are held in memory, so memory use is
+`O(sqrt(n) + segment_len)` rather than the `O(n)` of a full `totients` table.
+
+Iterating yields `(low, totients)` for one segment at a time, in ascending order, where `totients[i]` is φ(low + i).
+*/
+#[derive(Debug, Clone)]
+pub struct SegmentedTotients {
+    base_primes: Vec<u64>,
+    scratch: Scratch,
+    segment_len: usize,
+    low: u64,
+    high: u64,
+}
+
+impl SegmentedTotients {
+    /// Returns a `SegmentedTotients` over `0..=n` using segments of `DEFAULT_SEGMENT_LEN`
+    #[must_use]
+    pub fn new(n: u64) -> Self {
+        Self::with_segment_len(n, DEFAULT_SEGMENT_LEN)
+    }
+
+    /**
+    Returns a `SegmentedTotients` over `0..=n` using segments of `segment_len` values.
+    # Panics
+    Panics if `segment_len` is zero.
+    */
+    #[must_use]
+    pub fn with_segment_len(n: u64, segment_len: usize) -> Self {
+        assert!(segment_len > 0, "segment length must be non-zero");
+        let high = n.saturating_add(1);
+        Self {
+            base_primes: base_primes_below(high),
+            scratch: Scratch::default(),
+            segment_len,
+            low: 0,
+            high,
+        }
+    }
+
+    /// Returns the length of each segment
+    #[must_use]
+    pub const fn segment_len(&self) -> usize {
+        self.segment_len
+    }
+}
+
+impl Iterator for SegmentedTotients {
+    type Item = (u64, Vec<u64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.low >= self.high {
+            return None;
+        }
+        let low = self.low;
+        let len = to_index(self.high - low).min(self.segment_len);
+        let mut segment = vec![0; len];
+        totient_segment(&mut segment, low, &self.base_primes, &mut self.scratch);
+        self.low = low.saturating_add(len as u64);
+        Some((low, segment))
+    }
+}
@@ -0,0 +1,281 @@
+use crate::storage::coprime_sieve_prime_count;
+use crate::{SieveError, SieveStorage, SieveVecBool};
+
+/// Marker in `WheelSieve::positions` for residues that share a factor with the wheel's modulus.
+const NOT_ON_WHEEL: usize = usize::MAX;
+
+/// The wheels a `WheelSieve` can be built on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wheel {
+    /// Skips multiples of 2, 3 and 5, storing 8 of every 30 numbers.
+    Mod30,
+
+    /// Skips multiples of 2, 3, 5 and 7, storing 48 of every 210 numbers.
+    Mod210,
+}
+
+impl Wheel {
+    /// Returns the product of the wheel's primes
+    #[must_use]
+    pub const fn modulus(self) -> usize {
+        match self {
+            Self::Mod30 => 30,
+            Self::Mod210 => 210,
+        }
+    }
+
+    /// Returns the primes the wheel skips the multiples of
+    #[must_use]
+    pub const fn primes(self) -> &'static [usize] {
+        match self {
+            Self::Mod30 => &[2, 3, 5],
+            Self::Mod210 => &[2, 3, 5, 7],
+        }
+    }
+
+    /// Returns true if `n` shares no factor with the wheel's modulus
+    #[must_use]
+    pub fn is_coprime(self, n: usize) -> bool {
+        self.primes().iter().all(|&p| !n.is_multiple_of(p))
+    }
+}
+
+/**
+A sieve storing only the numbers coprime to a wheel's modulus (see `Wheel`).
+Index `i` holds the `i`th number coprime to the modulus, starting from 1.
+
+Marking steps across the wheel: the multiples `n * m` with `m` on the wheel split into one arithmetic progression
+per residue class, each of which is a plain `set_step_range_to_false` in index space.
+The wheel's own primes and their multiples are never stored, so they never show up in `values`.
+*/
+#[derive(Debug, Clone)]
+pub struct WheelSieve<S = SieveVecBool> {
+    storage: S,
+    wheel: Wheel,
+    limit: usize,
+    residues: Vec<usize>,
+    positions: Vec<usize>,
+}
+
+impl<S: SieveStorage> WheelSieve<S> {
+    /// Returns a `WheelSieve` covering the numbers below `limit` that are coprime to `wheel`'s modulus, all set to true
+    #[must_use]
+    pub fn new(limit: usize, wheel: Wheel) -> Self {
+        let modulus = wheel.modulus();
+        let residues: Vec<usize> = (1..modulus).filter(|&r| wheel.is_coprime(r)).collect();
+        let mut positions = vec![NOT_ON_WHEEL; modulus];
+        for (position, &residue) in residues.iter().enumerate() {
+            positions[residue] = position;
+        }
+        let len = limit / modulus * residues.len()
+            + residues.iter().filter(|&&r| r < limit % modulus).count();
+        Self {
+            storage: S::with_len(len),
+            wheel,
+            limit,
+            residues,
+            positions,
+        }
+    }
+
+    /// Returns the wheel this sieve is built on
+    #[must_use]
+    pub const fn wheel(&self) -> Wheel {
+        self.wheel
+    }
+
+    /// Returns the exclusive upper bound on the values this sieve covers
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the inner storage
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Returns the value stored at `index`
+    #[must_use]
+    pub fn value_of(&self, index: usize) -> usize {
+        let spokes = self.residues.len();
+        index / spokes * self.wheel.modulus() + self.residues[index % spokes]
+    }
+
+    /// Returns the index holding `value`, or `None` if `value` is not coprime to the wheel's modulus
+    #[must_use]
+    pub fn index_of(&self, value: usize) -> Option<usize> {
+        let modulus = self.wheel.modulus();
+        match self.positions[value % modulus] {
+            NOT_ON_WHEEL => None,
+            position => Some(value / modulus * self.residues.len() + position),
+        }
+    }
+
+    /// Returns whether `value` is still set, or `None` if `value` is not stored in this sieve
+    #[must_use]
+    pub fn get(&self, value: usize) -> Option<bool> {
+        self.storage.get(self.index_of(value)?)
+    }
+
+    /**
+    Sets `value` to false.
+    # Errors
+    Returns `SieveError::NotStored` if `value` is not coprime to the wheel's modulus or not below `self.limit()`.
+    */
+    pub fn set_false(&mut self, value: usize) -> Result<(), SieveError> {
+        match self.index_of(value) {
+            Some(index) if index < self.storage.len() => {
+                unsafe { self.storage.set_false_unchecked(index) };
+                Ok(())
+            }
+            _ => Err(SieveError::NotStored {
+                value,
+                limit: self.limit,
+            }),
+        }
+    }
+
+    /**
+    Returns the `(start, step_size)` in index space of each progression of stored values `n * m`, with `m` on the wheel and at least `from`.
+    Empty if `n` shares a factor with the modulus, since none of its multiples are stored.
+    */
+    fn progressions(&self, n: usize, from: usize) -> Vec<(usize, usize)> {
+        if !self.wheel.is_coprime(n) {
+            return Vec::new();
+        }
+        let modulus = self.wheel.modulus();
+        let base = from - from % modulus;
+        let step_size = n.saturating_mul(self.residues.len());
+        self.residues
+            .iter()
+            .filter_map(|&residue| {
+                let m = if residue < from % modulus {
+                    base.checked_add(modulus + residue)?
+                } else {
+                    base + residue
+                };
+                n.checked_mul(m)
+            })
+            .filter_map(|first| self.index_of(first))
+            .map(|start| (start, step_size))
+            .collect()
+    }
+
+    /**
+    Sets the stored multiples of `n` (`n * m` for every `m` on the wheel, including `n` itself) to false.
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false(&mut self, n: usize) -> Result<(), SieveError> {
+        if n == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        for (start, step_size) in self.progressions(n, 1) {
+            let start = start.min(len);
+            unsafe { self.storage.set_step_range_to_false(start, len, step_size) };
+        }
+        Ok(())
+    }
+
+    /**
+    Parallel version of `set_multiples_to_false`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `n` is zero.
+    */
+    pub fn set_multiples_to_false_par(&mut self, n: usize) -> Result<(), SieveError> {
+        if n == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        for (start, step_size) in self.progressions(n, 1) {
+            let start = start.min(len);
+            unsafe {
+                self.storage
+                    .set_step_range_to_false_par(start, len, step_size);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+    Crosses off the prime `p`: the stored multiples `p * m` with `m >= p` are set to false, leaving `p` itself set.
+    Does nothing for the wheel's own primes, whose multiples are never stored.
+
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        for (start, step_size) in self.progressions(p, p) {
+            let start = start.min(len);
+            unsafe { self.storage.set_step_range_to_false(start, len, step_size) };
+        }
+        Ok(())
+    }
+
+    /**
+    Parallel version of `cross_off_prime`.
+    # Errors
+    Returns `SieveError::ZeroStep` if `p` is zero.
+    */
+    pub fn cross_off_prime_par(&mut self, p: usize) -> Result<(), SieveError> {
+        if p == 0 {
+            return Err(SieveError::ZeroStep);
+        }
+        let len = self.storage.len();
+        for (start, step_size) in self.progressions(p, p) {
+            let start = start.min(len);
+            unsafe {
+                self.storage
+                    .set_step_range_to_false_par(start, len, step_size);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+    Returns the number of primes below `self.limit()` in a finished sieve:
+    the values other than 1 that are still set, plus the wheel's own primes.
+    Every prime below `sqrt(self.limit())` that is not on the wheel must have been crossed off for the count to be exact.
+    */
+    #[must_use]
+    pub fn count_primes(&self) -> usize {
+        coprime_sieve_prime_count(
+            &self.storage,
+            self.storage.count_ones(),
+            self.wheel_primes_below_limit(),
+        )
+    }
+
+    /// Parallel version of `count_primes`
+    #[must_use]
+    pub fn count_primes_par(&self) -> usize {
+        coprime_sieve_prime_count(
+            &self.storage,
+            self.storage.count_ones_par(),
+            self.wheel_primes_below_limit(),
+        )
+    }
+
+    /// Returns how many of the wheel's primes, which are never stored, are below `self.limit()`
+    fn wheel_primes_below_limit(&self) -> usize {
+        self.wheel
+            .primes()
+            .iter()
+            .filter(|&&p| p < self.limit)
+            .count()
+    }
+
+    /// Returns an iterator over the values that are still set, in ascending order
+    pub fn values(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.storage.len())
+            .filter(|&index| self.storage.get(index) == Some(true))
+            .map(|index| self.value_of(index))
+    }
+}
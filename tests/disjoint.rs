@@ -0,0 +1,92 @@
+use std::{panic, thread};
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use sieves::DisjointWriter;
+
+const LEN: usize = if cfg!(miri) { 100 } else { 10_000 };
+
+#[test]
+fn disjoint_parallel_writes_land() {
+    let mut vec = vec![0; LEN];
+    let writer = DisjointWriter::new(&mut vec);
+    (0..LEN).into_par_iter().for_each_init(
+        || writer.task(),
+        |task, index| unsafe { task.write(index, index * 2) },
+    );
+    assert!(
+        vec.iter()
+            .enumerate()
+            .all(|(index, &value)| value == index * 2)
+    );
+}
+
+#[test]
+fn same_value_from_two_tasks_is_allowed() {
+    let mut vec = vec![true; 4];
+    let writer = DisjointWriter::new(&mut vec);
+    thread::scope(|s| {
+        s.spawn(|| unsafe { writer.task().write(2, false) });
+    });
+    unsafe { writer.task().write(2, false) };
+    assert_eq!(vec, [true, true, false, true]);
+}
+
+#[test]
+fn one_task_may_overwrite_its_own_writes() {
+    let mut vec = vec![0; 4];
+    let writer = DisjointWriter::new(&mut vec);
+    let task = writer.task();
+    unsafe {
+        task.write(1, 1);
+        task.write(1, 2);
+    }
+    assert_eq!(vec, [0, 2, 0, 0]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "written with different values")]
+fn conflicting_values_from_two_threads_panic() {
+    let mut vec = vec![0; 4];
+    let writer = DisjointWriter::new(&mut vec);
+    for value in [1, 2] {
+        thread::scope(|s| {
+            let handle = s.spawn(|| unsafe { writer.task().write(0, value) });
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
+            }
+        });
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "written with different values")]
+fn conflicting_values_from_two_tasks_on_one_thread_panic() {
+    let mut vec = vec![0; 4];
+    let writer = DisjointWriter::new(&mut vec);
+    // On a one-thread pool both halves of the join run on the same worker.
+    if let Ok(pool) = rayon::ThreadPoolBuilder::new().num_threads(1).build() {
+        pool.install(|| {
+            rayon::join(
+                || unsafe { writer.task().write(0, 1) },
+                || unsafe { writer.task().write(0, 2) },
+            )
+        });
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "written with different values")]
+fn agreeing_write_does_not_hide_an_earlier_conflict() {
+    let mut vec = vec![0; 4];
+    let writer = DisjointWriter::new(&mut vec);
+    let (first, second) = (writer.task(), writer.task());
+    unsafe {
+        first.write(0, 1);
+        second.write(0, 1);
+        // `first` wrote 1 here, so `second` changing it to 2 is still a conflict.
+        second.write(0, 2);
+    }
+}
@@ -0,0 +1,45 @@
+//! Loom models of the crate's concurrent marking patterns.
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+//!
+//! `AtomicSieveVecBit` is built on loom's atomics under `cfg(loom)`, so its methods are checked directly.
+//! The `SieveVecBool` and `SieveVecBit` parallel paths hand each rayon task its own `&mut` chunk, which loom cannot see;
+//! they are checked against a sequential reference, under Miri too, in `tests/reference.rs`.
+
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+use sieves::{AtomicSieveVecBit, SieveError};
+
+#[test]
+fn atomic_clears_in_one_word_are_not_lost() {
+    loom::model(|| {
+        let sieve = Arc::new(AtomicSieveVecBit::with_len(8));
+        let handles: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|index| {
+                let sieve = Arc::clone(&sieve);
+                thread::spawn(move || sieve.set_false(index))
+            })
+            .collect();
+        assert_eq!(sieve.set_false(3), Ok(()));
+        for handle in handles {
+            assert_eq!(handle.join().ok(), Some(Ok::<(), SieveError>(())));
+        }
+        let bits: Vec<bool> = (0..8).map(|index| sieve.get(index) == Some(true)).collect();
+        assert_eq!(bits, [true, false, false, false, true, true, true, true]);
+    });
+}
+
+#[test]
+fn atomic_overlapping_multiples_are_not_lost() {
+    loom::model(|| {
+        let sieve = Arc::new(AtomicSieveVecBit::with_len(7));
+        let other = Arc::clone(&sieve);
+        let handle = thread::spawn(move || other.set_multiples_to_false(2));
+        assert_eq!(sieve.set_multiples_to_false(3), Ok(()));
+        assert_eq!(handle.join().ok(), Some(Ok(())));
+        let bits: Vec<bool> = (0..7).map(|index| sieve.get(index) == Some(true)).collect();
+        assert_eq!(bits, [true, true, false, false, false, true, false]);
+    });
+}
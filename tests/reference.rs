@@ -0,0 +1,703 @@
+//! Compares the parallel marking paths against a trivially-correct sequential sieve.
+//! Sizes shrink under Miri so the whole suite stays runnable with
+//! `MIRIFLAGS="-Zmiri-tree-borrows -Zmiri-ignore-leaks" cargo +nightly miri test --test reference`.
+//! Tree borrows is needed because rayon's crossbeam-epoch dependency trips stacked borrows,
+//! and rayon's global pool threads are never joined.
+
+use sieves::{
+    Algorithm, AtomicSieveVecBit, MarkingMode, OddSieve, RankSelect, SegmentedSieve,
+    SegmentedTotients, SieveConfig, SieveError, SieveStorage, SieveVecBit, SieveVecBool, SpfSieve,
+    Wheel, WheelSieve, divisor_counts, divisor_counts_par, divisor_counts_par_with, divisor_sums,
+    divisor_sums_par, divisor_sums_par_with, linear_sieve, mobius, mobius_par, mobius_par_with,
+    multiplicative_sieve, multiplicative_sieve_par_with, prime_count, prime_count_par,
+    prime_count_par_with, primes, primes_in_range, primes_up_to, primes_up_to_par,
+    primes_up_to_par_with, primes_up_to_with, squarefree, squarefree_par, squarefree_par_with,
+    totients, totients_in_range, totients_par, totients_par_with,
+};
+
+const LEN: usize = if cfg!(miri) { 300 } else { 100_000 };
+
+/// Bases exercised by the multiple-marking tests, chosen to overlap heavily.
+const BASES: &[usize] = &[2, 3, 4, 6, 7, 10, 64, 97, 150, 299];
+
+/// Clears `(start..stop).step_by(step_size)` in a plain `Vec<bool>`, one index at a time
+fn reference_step_range(len: usize, start: usize, stop: usize, step_size: usize) -> Vec<bool> {
+    let mut vec = vec![true; len];
+    for index in (start..stop).step_by(step_size) {
+        vec[index] = false;
+    }
+    vec
+}
+
+/// Clears every multiple of every element of `bases`, including the base itself
+fn reference_multiples(len: usize, bases: &[usize]) -> Vec<bool> {
+    (0..len)
+        .map(|index| !bases.iter().any(|&n| index >= n && index % n == 0))
+        .collect()
+}
+
+/// Returns the smallest prime factor of `n >= 2` by trial division
+fn reference_spf(n: u32) -> u32 {
+    (2..=n.isqrt()).find(|&d| n.is_multiple_of(d)).unwrap_or(n)
+}
+
+/// Returns Euler's totient of `n` by counting the `k` in `1..=n` coprime to it
+fn reference_totient(n: u64) -> u64 {
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    (1..=n).filter(|&k| gcd(n, k) == 1).count() as u64
+}
+
+/// Returns the Möbius function of `n` by trial division
+fn reference_mobius(n: usize) -> i8 {
+    if n == 0 {
+        return 0;
+    }
+    let mut rest = n;
+    let mut mu = 1;
+    for d in 2..=n {
+        if d * d > rest {
+            break;
+        }
+        if rest.is_multiple_of(d) {
+            rest /= d;
+            if rest.is_multiple_of(d) {
+                return 0;
+            }
+            mu = -mu;
+        }
+    }
+    if rest > 1 { -mu } else { mu }
+}
+
+/// Returns every prime up to `n` by trial division
+fn reference_primes(n: usize) -> Vec<u64> {
+    (2..=n)
+        .filter(|&candidate| {
+            (2..candidate)
+                .take_while(|d| d * d <= candidate)
+                .all(|d| candidate % d != 0)
+        })
+        .map(|p| p as u64)
+        .collect()
+}
+
+#[test]
+fn step_range_par_matches_reference() -> Result<(), SieveError> {
+    for (start, stop, step_size) in [
+        (0, LEN, 1),
+        (1, LEN, 2),
+        (5, LEN - 3, 7),
+        (LEN / 2, LEN, 13),
+        (3, 3, 5),
+    ] {
+        let mut sieve = SieveVecBool::with_len(LEN);
+        sieve.set_step_range_to_false_par_checked(start, stop, step_size)?;
+        assert_eq!(
+            sieve.into_inner(),
+            reference_step_range(LEN, start, stop, step_size)
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn step_range_par_matches_sequential() -> Result<(), SieveError> {
+    let mut sequential = SieveVecBool::with_len(LEN);
+    let mut parallel = SieveVecBool::with_len(LEN);
+    for &n in BASES {
+        sequential.set_multiples_to_false_checked(n)?;
+        parallel.set_multiples_to_false_par_checked(n)?;
+    }
+    assert_eq!(sequential.into_inner(), parallel.into_inner());
+    Ok(())
+}
+
+#[test]
+fn multiples_of_slice_par_matches_reference() -> Result<(), SieveError> {
+    let mut sieve = SieveVecBool::with_len(LEN);
+    sieve.set_multiples_of_slice_to_false_par_checked(BASES)?;
+    assert_eq!(sieve.into_inner(), reference_multiples(LEN, BASES));
+    Ok(())
+}
+
+#[test]
+fn multiples_including_self_match_reference() -> Result<(), SieveError> {
+    let mut bools = SieveVecBool::with_len(LEN);
+    let mut bits = SieveVecBit::with_len(LEN);
+    for &n in BASES {
+        bools.set_multiples_including_self(n)?;
+        bits.set_multiples_including_self(n)?;
+    }
+    let expected = reference_multiples(LEN, BASES);
+    assert_eq!(bits.to_vec_bool(), expected);
+    assert_eq!(bools.into_inner(), expected);
+
+    // Unlike `cross_off_prime`, the base itself is cleared too.
+    let mut sieve = SieveVecBit::with_len(10);
+    sieve.set_multiples_including_self(3)?;
+    assert_eq!(sieve.get(3), Some(false));
+    assert_eq!(
+        sieve.set_multiples_including_self(0),
+        Err(SieveError::ZeroStep)
+    );
+    Ok(())
+}
+
+#[test]
+fn bit_backend_matches_reference() -> Result<(), SieveError> {
+    let mut sieve = SieveVecBit::with_len(LEN);
+    sieve.set_multiples_of_slice_to_false_par_checked(BASES)?;
+    assert_eq!(sieve.to_vec_bool(), reference_multiples(LEN, BASES));
+
+    let mut sieve = SieveVecBit::with_len(LEN);
+    sieve.set_step_range_to_false_par_checked(5, LEN - 3, 7)?;
+    assert_eq!(
+        sieve.to_vec_bool(),
+        reference_step_range(LEN, 5, LEN - 3, 7)
+    );
+    Ok(())
+}
+
+#[test]
+fn atomic_backend_matches_reference() -> Result<(), SieveError> {
+    let sieve = AtomicSieveVecBit::with_len(LEN);
+    sieve.set_multiples_of_slice_to_false_par(BASES)?;
+    assert_eq!(
+        sieve.into_sieve_vec_bit().to_vec_bool(),
+        reference_multiples(LEN, BASES)
+    );
+    Ok(())
+}
+
+#[test]
+fn every_adaptive_mode_matches_reference() -> Result<(), SieveError> {
+    for mode in [
+        MarkingMode::Auto,
+        MarkingMode::Sequential,
+        MarkingMode::Chunked,
+        MarkingMode::Segmented,
+    ] {
+        let config = SieveConfig::new().with_mode(mode).with_segment_len(97);
+        let mut sieve = SieveVecBool::with_len(LEN);
+        sieve.set_multiples_of_slice_to_false_adaptive(BASES, &config)?;
+        assert_eq!(sieve.into_inner(), reference_multiples(LEN, BASES));
+
+        let mut sieve = SieveVecBool::with_len(LEN);
+        sieve.set_step_range_to_false_adaptive(5, LEN - 3, 7, &config)?;
+        assert_eq!(sieve.into_inner(), reference_step_range(LEN, 5, LEN - 3, 7));
+    }
+    Ok(())
+}
+
+#[test]
+fn checked_methods_reject_bad_ranges() {
+    let mut sieve = SieveVecBool::with_len(10);
+    assert_eq!(
+        sieve.set_step_range_to_false_par_checked(0, 10, 0),
+        Err(SieveError::ZeroStep)
+    );
+    assert_eq!(
+        sieve.set_step_range_to_false_par_checked(6, 4, 1),
+        Err(SieveError::StartAfterStop { start: 6, stop: 4 })
+    );
+    assert_eq!(
+        sieve.set_step_range_to_false_par_checked(1, 12, 3),
+        Err(SieveError::OutOfRange { index: 10, len: 10 })
+    );
+    assert_eq!(
+        sieve.set_multiples_of_slice_to_false_par_checked(&[2, 0]),
+        Err(SieveError::ZeroStep)
+    );
+    assert_eq!(sieve.into_inner(), vec![true; 10]);
+}
+
+#[test]
+fn huge_steps_stop_instead_of_wrapping() -> Result<(), SieveError> {
+    // `5 + usize::MAX` overflows, which used to wrap around and clear 4, 3, 2, 1 and 0.
+    let expected = reference_step_range(10, 5, 10, usize::MAX);
+    let mut sieve = SieveVecBool::with_len(10);
+    sieve.set_step_range_to_false_checked(5, 10, usize::MAX)?;
+    assert_eq!(sieve.into_inner(), expected);
+
+    let mut sieve = SieveVecBool::with_len(10);
+    sieve.set_step_range_to_false_par_checked(5, 10, usize::MAX)?;
+    assert_eq!(sieve.into_inner(), expected);
+
+    let mut sieve = SieveVecBool::with_len(10);
+    sieve.set_multiples_of_slice_to_false_par_checked(&[usize::MAX, 5])?;
+    assert_eq!(sieve.into_inner(), reference_multiples(10, &[5]));
+
+    let mut sieve = SieveVecBit::with_len(10);
+    sieve.set_step_range_to_false_checked(5, 10, usize::MAX)?;
+    assert_eq!(sieve.to_vec_bool(), expected);
+
+    let mut sieve = SieveVecBit::with_len(10);
+    sieve.set_step_range_to_false_par_checked(5, 10, usize::MAX)?;
+    assert_eq!(sieve.to_vec_bool(), expected);
+
+    let mut sieve = SieveVecBit::with_len(10);
+    sieve.set_multiples_of_slice_to_false_par_checked(&[usize::MAX, 5])?;
+    assert_eq!(sieve.to_vec_bool(), reference_multiples(10, &[5]));
+
+    for mode in [
+        MarkingMode::Sequential,
+        MarkingMode::Chunked,
+        MarkingMode::Segmented,
+    ] {
+        let config = SieveConfig::new().with_mode(mode).with_segment_len(3);
+        let mut sieve = SieveVecBool::with_len(10);
+        sieve.set_step_range_to_false_adaptive(5, 10, usize::MAX, &config)?;
+        assert_eq!(sieve.into_inner(), expected);
+
+        let mut sieve = SieveVecBool::with_len(10);
+        sieve.set_multiples_of_slice_to_false_adaptive(&[usize::MAX, 5], &config)?;
+        assert_eq!(sieve.into_inner(), reference_multiples(10, &[5]));
+    }
+    Ok(())
+}
+
+#[test]
+fn odd_sieve_matches_reference() -> Result<(), SieveError> {
+    let odd_values =
+        |vec: Vec<bool>| -> Vec<usize> { (1..LEN).step_by(2).filter(|&v| vec[v]).collect() };
+
+    // Even bases have no odd multiples, so only the odd ones mark anything.
+    let mut sequential = OddSieve::<SieveVecBool>::new(LEN);
+    let mut parallel = OddSieve::<SieveVecBit>::new(LEN);
+    for &n in BASES {
+        sequential.set_multiples_to_false(n)?;
+        parallel.set_multiples_to_false_par(n)?;
+    }
+    let expected = odd_values(reference_multiples(LEN, BASES));
+    assert_eq!(sequential.values().collect::<Vec<_>>(), expected);
+    assert_eq!(parallel.values().collect::<Vec<_>>(), expected);
+
+    // Crossing off the odd primes leaves 1 and the odd primes.
+    let mut sieve = OddSieve::<SieveVecBit>::new(LEN);
+    for p in (3..=LEN.isqrt()).step_by(2) {
+        sieve.cross_off_prime_par(p)?;
+    }
+    let expected: Vec<u64> = std::iter::once(1)
+        .chain(reference_primes(LEN).into_iter().skip(1))
+        .collect();
+    assert!(sieve.values().map(|v| v as u64).eq(expected));
+
+    sieve.set_false(1)?;
+    assert_eq!(sieve.get(1), Some(false));
+    for value in [4, LEN + 1] {
+        assert_eq!(
+            sieve.set_false(value),
+            Err(SieveError::NotStored { value, limit: LEN })
+        );
+    }
+    assert_eq!(sieve.set_multiples_to_false(0), Err(SieveError::ZeroStep));
+    assert_eq!(sieve.cross_off_prime_par(0), Err(SieveError::ZeroStep));
+    Ok(())
+}
+
+#[test]
+fn odd_sieve_counts_primes_below_small_limits() -> Result<(), SieveError> {
+    // `new(3)` stores only the value 1, just like `new(2)`, yet still covers the prime 2.
+    for limit in 0..40 {
+        let mut sieve = OddSieve::<SieveVecBit>::new(limit);
+        for p in (3..=limit.isqrt()).step_by(2) {
+            sieve.cross_off_prime(p)?;
+        }
+        let expected = reference_primes(limit.saturating_sub(1)).len();
+        assert_eq!(sieve.limit(), limit);
+        assert_eq!(sieve.count_primes(), expected, "primes below {limit}");
+        assert_eq!(sieve.count_primes_par(), expected, "primes below {limit}");
+    }
+    Ok(())
+}
+
+#[test]
+fn wheel_sieves_match_reference() -> Result<(), SieveError> {
+    for wheel in [Wheel::Mod30, Wheel::Mod210] {
+        let on_wheel = |vec: Vec<bool>| -> Vec<usize> {
+            (1..LEN)
+                .filter(|&v| wheel.is_coprime(v) && vec[v])
+                .collect()
+        };
+
+        // Bases sharing a factor with the modulus have no stored multiples, so only the others mark anything.
+        let mut sequential = WheelSieve::<SieveVecBool>::new(LEN, wheel);
+        let mut parallel = WheelSieve::<SieveVecBit>::new(LEN, wheel);
+        for &n in BASES {
+            sequential.set_multiples_to_false(n)?;
+            parallel.set_multiples_to_false_par(n)?;
+        }
+        let expected = on_wheel(reference_multiples(LEN, BASES));
+        assert_eq!(sequential.values().collect::<Vec<_>>(), expected);
+        assert_eq!(parallel.values().collect::<Vec<_>>(), expected);
+
+        // Crossing off every prime leaves 1 and the primes that are not on the wheel.
+        let mut sequential = WheelSieve::<SieveVecBool>::new(LEN, wheel);
+        let mut parallel = WheelSieve::<SieveVecBit>::new(LEN, wheel);
+        for p in 2..=LEN.isqrt() {
+            sequential.cross_off_prime(p)?;
+            parallel.cross_off_prime_par(p)?;
+        }
+        let expected: Vec<u64> = std::iter::once(1)
+            .chain(
+                reference_primes(LEN)
+                    .into_iter()
+                    .filter(|&p| wheel.primes().iter().all(|&w| w as u64 != p)),
+            )
+            .collect();
+        assert!(
+            sequential
+                .values()
+                .map(|v| v as u64)
+                .eq(expected.iter().copied())
+        );
+        assert!(
+            parallel
+                .values()
+                .map(|v| v as u64)
+                .eq(expected.iter().copied())
+        );
+        assert_eq!(sequential.count_primes(), reference_primes(LEN).len());
+        assert_eq!(parallel.count_primes_par(), reference_primes(LEN).len());
+
+        for value in [wheel.modulus(), LEN + 1] {
+            assert_eq!(
+                sequential.set_false(value),
+                Err(SieveError::NotStored { value, limit: LEN })
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn primes_match_trial_division() {
+    let expected = reference_primes(LEN);
+    assert_eq!(primes_up_to(LEN), expected);
+    assert_eq!(primes_up_to_par(LEN), expected);
+    for algorithm in [Algorithm::Eratosthenes, Algorithm::Linear] {
+        assert_eq!(primes_up_to_with(LEN, algorithm), expected);
+        assert_eq!(primes_up_to_with(1, algorithm), Vec::<u64>::new());
+    }
+}
+
+#[test]
+fn segmented_sieve_matches_primes_up_to() {
+    let expected = primes_up_to(LEN);
+    // An odd segment length puts segment boundaries on both odd and even values.
+    let segments: Vec<Vec<u64>> = SegmentedSieve::with_segment_len(LEN as u64, 97).collect();
+    assert_eq!(segments.len(), (LEN + 1).div_ceil(97));
+    assert_eq!(segments.concat(), expected);
+    assert_eq!(
+        SegmentedSieve::new(LEN as u64)
+            .flatten()
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(SegmentedSieve::new(1).flatten().count(), 0);
+}
+
+#[test]
+fn unbounded_primes_match_primes_up_to() {
+    let expected = primes_up_to(LEN);
+    // `LEN` runs past the first few segments, so the base primes have to grow along the way.
+    let taken: Vec<u64> = primes().take_while(|&p| p <= LEN as u64).collect();
+    assert_eq!(taken, expected);
+    assert_eq!(primes().next(), Some(2));
+    assert_eq!(primes().nth(24), Some(97));
+    assert_eq!(primes().nth(expected.len() - 1), expected.last().copied());
+}
+
+#[test]
+fn primes_in_range_matches_reference() {
+    let expected = reference_primes(LEN);
+    assert_eq!(primes_in_range(0, LEN as u64 + 1), expected);
+    let (lo, hi) = (LEN as u64 / 3, LEN as u64 / 2);
+    let window: Vec<u64> = expected
+        .iter()
+        .copied()
+        .filter(|p| (lo..hi).contains(p))
+        .collect();
+    assert_eq!(primes_in_range(lo, hi), window);
+    assert_eq!(primes_in_range(hi, lo), Vec::<u64>::new());
+    assert_eq!(primes_in_range(0, 2), Vec::<u64>::new());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn primes_in_range_far_from_zero_matches_trial_division() {
+    let (lo, hi): (u64, u64) = (1_000_000_000_000, 1_000_000_010_000);
+    // Every composite below `hi` has a prime factor up to `sqrt(hi)`, which is just over 10^6.
+    let divisors = reference_primes(1_000_001);
+    let expected: Vec<u64> = (lo..hi)
+        .filter(|&candidate| divisors.iter().all(|&d| candidate % d != 0))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(primes_in_range(lo, hi), expected);
+}
+
+#[test]
+fn dedicated_pool_is_used_and_matches_reference() -> Result<(), rayon::ThreadPoolBuildError> {
+    let config = SieveConfig::new().with_num_threads(2)?;
+    assert_eq!(config.install(rayon::current_num_threads), 2);
+
+    let expected = reference_primes(LEN);
+    assert_eq!(primes_up_to_par_with(LEN, &config), expected);
+    assert_eq!(
+        SegmentedSieve::with_segment_len(LEN as u64, 1000).primes_par_with(&config),
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn prime_count_matches_primes_up_to() {
+    let primes = primes_up_to(LEN);
+    for x in (0..LEN as u64).step_by(if cfg!(miri) { 37 } else { 997 }) {
+        let expected = primes.partition_point(|&p| p <= x) as u64;
+        assert_eq!(prime_count(x), expected, "π({x})");
+        assert_eq!(prime_count_par(x), expected, "π({x})");
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn prime_count_matches_primes_up_to_past_the_direct_sieve() {
+    // Everything from 2^20 up goes through the special-leaf method rather than a plain sieve.
+    let primes = primes_up_to(20_000_000);
+    let squares = [1025 * 1025, 2048 * 2048, 4001 * 4001];
+    let xs = (1 << 20..20_000_000)
+        .step_by(99_991)
+        .chain(squares.into_iter().flat_map(|s| [s - 1, s, s + 1]));
+    for x in xs {
+        let expected = primes.partition_point(|&p| p <= x) as u64;
+        assert_eq!(prime_count(x), expected, "π({x})");
+        assert_eq!(prime_count_par(x), expected, "π({x})");
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn prime_count_par_with_uses_config() -> Result<(), rayon::ThreadPoolBuildError> {
+    // Short segments make both sieves carry counts across many segment boundaries.
+    let config = SieveConfig::new()
+        .with_num_threads(2)?
+        .with_segment_len(1000);
+    for (x, expected) in [(1 << 20, 82_025), (100_000_000, 5_761_455)] {
+        assert_eq!(prime_count_par_with(x, &config), expected);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn prime_count_matches_known_values() {
+    for (x, expected) in [
+        (1_000_000_000, 50_847_534),
+        (10_000_000_000, 455_052_511),
+        (100_000_000_000, 4_118_054_813),
+        (1_000_000_000_000, 37_607_912_018),
+        (10_000_000_000_000, 346_065_536_839),
+    ] {
+        assert_eq!(prime_count(x), expected);
+        assert_eq!(prime_count_par(x), expected);
+    }
+}
+
+#[test]
+fn counts_match_reference() -> Result<(), SieveError> {
+    let expected = reference_primes(LEN).len();
+
+    let mut sieve = SieveVecBool::with_len(LEN + 1);
+    let mut bits = SieveVecBit::with_len(LEN + 1);
+    let mut odd = OddSieve::<SieveVecBit>::new(LEN + 1);
+    let mut wheel = WheelSieve::<SieveVecBool>::new(LEN + 1, Wheel::Mod30);
+    for p in 2..=LEN.isqrt() {
+        sieve.cross_off_prime(p)?;
+        bits.cross_off_prime(p)?;
+        odd.cross_off_prime(p)?;
+        if !Wheel::Mod30.primes().contains(&p) {
+            wheel.cross_off_prime(p)?;
+        }
+    }
+    // 0 and 1 are still set, so they show up in `count_ones` but not in `count_primes`.
+    assert_eq!(sieve.count_ones(), expected + 2);
+    assert_eq!(sieve.count_ones_par(), expected + 2);
+    assert_eq!(bits.count_ones(), expected + 2);
+    assert_eq!(bits.count_ones_par(), expected + 2);
+    for count in [
+        sieve.count_primes(),
+        sieve.count_primes_par(),
+        bits.count_primes(),
+        bits.count_primes_par(),
+        odd.count_primes(),
+        odd.count_primes_par(),
+        wheel.count_primes(),
+        wheel.count_primes_par(),
+    ] {
+        assert_eq!(count, expected);
+    }
+    assert_eq!(AtomicSieveVecBit::from(bits).count_ones(), expected + 2);
+    Ok(())
+}
+
+#[test]
+fn rank_select_matches_reference() -> Result<(), SieveError> {
+    let vec = reference_multiples(LEN, BASES);
+    let set: Vec<usize> = (0..LEN).filter(|&index| vec[index]).collect();
+    for index in [
+        SieveVecBool::from(vec.clone()).into(),
+        RankSelect::from(SieveVecBit::from(vec)),
+    ] {
+        assert_eq!(index.count_ones(), set.len());
+        for k in (0..=LEN + 1).step_by(7) {
+            assert_eq!(index.rank(k), set.partition_point(|&i| i < k), "rank({k})");
+        }
+        for (j, &expected) in set.iter().enumerate() {
+            assert_eq!(index.select(j), Some(expected), "select({j})");
+        }
+        assert_eq!(index.select(set.len()), None);
+    }
+
+    // Over a prime sieve with 0 and 1 cleared, rank counts primes and select finds the nth prime.
+    let primes = primes_up_to(LEN);
+    let mut sieve = SieveVecBool::with_len(LEN + 1);
+    sieve.set_false(0)?;
+    sieve.set_false(1)?;
+    for p in 2..=LEN.isqrt() {
+        sieve.cross_off_prime(p)?;
+    }
+    let index = RankSelect::from(sieve);
+    assert_eq!(index.rank(LEN + 1), primes.len());
+    assert_eq!(index.rank(100), 25);
+    assert_eq!(index.select(24), Some(97));
+    Ok(())
+}
+
+#[test]
+fn spf_matches_trial_division() -> Result<(), std::num::TryFromIntError> {
+    let n = u32::try_from(LEN)?;
+    let sieve = SpfSieve::new(n);
+    assert_eq!(sieve.limit(), n);
+    assert_eq!(SpfSieve::new_par(n), sieve);
+    assert_eq!(
+        SpfSieve::new_par_with(n, &SieveConfig::new().with_segment_len(97)),
+        sieve
+    );
+    assert_eq!(SpfSieve::with_algorithm(n, Algorithm::Linear), sieve);
+    let (primes, linear) = linear_sieve(n);
+    assert_eq!(linear, sieve);
+    assert!(
+        primes
+            .iter()
+            .map(|&p| u64::from(p))
+            .eq(reference_primes(LEN))
+    );
+    assert_eq!(sieve.get(1), None);
+    assert_eq!(sieve.get(n + 1), None);
+    for m in 2..=n {
+        assert_eq!(sieve.get(m), Some(reference_spf(m)), "spf({m})");
+    }
+
+    assert_eq!(sieve.factorize(0), None);
+    assert_eq!(sieve.factorize(1), Some(Vec::new()));
+    assert_eq!(sieve.factorize(360), Some(vec![2, 2, 2, 3, 3, 5]));
+    for m in (1..=n).step_by(31) {
+        let factors = sieve.factorize(m).unwrap_or_default();
+        assert!(factors.is_sorted());
+        assert!(factors.iter().all(|&p| sieve.is_prime(p) == Some(true)));
+        assert_eq!(factors.iter().product::<u32>(), m);
+    }
+    Ok(())
+}
+
+#[test]
+fn totients_match_reference() -> Result<(), std::num::TryFromIntError> {
+    // Counting coprimes is quadratic, so check a smaller table exhaustively.
+    let n: u64 = if cfg!(miri) { 60 } else { 2_000 };
+    let expected: Vec<u64> = (0..=n).map(reference_totient).collect();
+    assert_eq!(totients(n), expected);
+    assert_eq!(totients_par(n), expected);
+    let small = u32::try_from(n)?;
+    let config = SieveConfig::new().with_segment_len(7);
+    assert!(
+        totients_par_with(small, &config)
+            .into_iter()
+            .map(u64::from)
+            .eq(expected.iter().copied())
+    );
+    assert_eq!(
+        totients_in_range(n / 3, n),
+        expected[usize::try_from(n / 3)?..usize::try_from(n)?]
+    );
+    assert_eq!(totients_in_range(n, n / 3), Vec::<u64>::new());
+
+    let mut segments = Vec::new();
+    for (low, segment) in SegmentedTotients::with_segment_len(n, 97) {
+        assert_eq!(low, segments.len() as u64);
+        segments.extend(segment);
+    }
+    assert_eq!(segments, expected);
+
+    // The full-size table agrees with the prime counts: φ(p) = p - 1 exactly for primes.
+    let table = totients(LEN as u64);
+    let primes = (2..=LEN).filter(|&m| table[m] == m as u64 - 1).count();
+    assert_eq!(primes, reference_primes(LEN).len());
+    Ok(())
+}
+
+#[test]
+fn mobius_and_squarefree_match_reference() {
+    let expected: Vec<i8> = (0..=LEN).map(reference_mobius).collect();
+    let config = SieveConfig::new().with_segment_len(97);
+    assert_eq!(mobius(LEN), expected);
+    assert_eq!(mobius_par(LEN), expected);
+    assert_eq!(mobius_par_with(LEN, &config), expected);
+
+    let expected: Vec<bool> = expected.iter().map(|&mu| mu != 0).collect();
+    assert_eq!(squarefree(LEN).into_inner(), expected);
+    assert_eq!(squarefree_par(LEN).into_inner(), expected);
+    for mode in [
+        MarkingMode::Sequential,
+        MarkingMode::Chunked,
+        MarkingMode::Segmented,
+    ] {
+        let config = config.clone().with_mode(mode);
+        assert_eq!(squarefree_par_with(LEN, &config).into_inner(), expected);
+    }
+    assert_eq!(mobius(0), [0]);
+    assert_eq!(squarefree(0).into_inner(), [false]);
+}
+
+#[test]
+fn multiplicative_sieves_match_reference() -> Result<(), std::num::TryFromIntError> {
+    // Listing divisors is quadratic, so check a smaller table exhaustively.
+    let n = if cfg!(miri) { 60 } else { 3_000 };
+    let divisors = |m: usize| (1..=m).filter(move |d| m.is_multiple_of(*d));
+    let counts: Vec<u32> = (0..=n)
+        .map(|m| u32::try_from(divisors(m).count()))
+        .collect::<Result<_, _>>()?;
+    let sums: Vec<u64> = (0..=n).map(|m| divisors(m).sum::<usize>() as u64).collect();
+    assert_eq!(divisor_counts(n), counts);
+    assert_eq!(divisor_counts_par(n), counts);
+    assert_eq!(divisor_sums(n), sums);
+    assert_eq!(divisor_sums_par(n), sums);
+    let config = SieveConfig::new().with_segment_len(97);
+    assert_eq!(divisor_counts_par_with(n, &config), counts);
+    assert_eq!(divisor_sums_par_with(n, &config), sums);
+
+    // Any multiplicative function can be supplied through f(p^k); φ(p^k) = p^(k - 1) (p - 1) recovers the totient table.
+    let totient = |p: u64, k: u32| p.pow(k - 1) * (p - 1);
+    let expected = totients(LEN as u64);
+    assert_eq!(multiplicative_sieve(LEN, totient), expected);
+    assert_eq!(
+        multiplicative_sieve_par_with(LEN, totient, &config),
+        expected
+    );
+    Ok(())
+}